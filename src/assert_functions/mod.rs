@@ -2,7 +2,7 @@
 mod test_vectors;
 
 use super::*;
-use crate::ciphertext::{ClearString, GenericPattern};
+use fhe_strings::{ClearString, GenericPattern};
 use std::time::Duration;
 
 fn result_message<T>(str: &str, expected: T, dec: T, dur: Duration)
@@ -86,7 +86,7 @@ where
 
 impl Keys {
     pub fn assert_len(&self, str: &str, str_pad: Option<u32>) {
-        let expected = str.len();
+        let expected = str.chars().count();
 
         let enc_str = FheString::new(&self.ck, str, str_pad);
 
@@ -95,8 +95,8 @@ impl Keys {
         let end = Instant::now();
 
         let dec = match result {
-            FheStringLen::NoPadding(clear_len) => clear_len,
-            FheStringLen::Padding(enc_len) => self.ck.key().decrypt_radix::<u32>(&enc_len) as usize,
+            FheStringCharLen::Clear(clear_len) => clear_len,
+            FheStringCharLen::Enc(enc_len) => self.ck.key().decrypt_radix::<u32>(&enc_len) as usize,
         };
 
         println!("\n\x1b[1mLen:\x1b[0m");
@@ -494,8 +494,6 @@ impl Keys {
         result_message_clear_rhs(str, rhs, expected_ne, dec_ne, end.duration_since(start));
         assert_eq!(dec_ne, expected_ne);
 
-        let enc_rhs = FheString::new(&self.ck, rhs, rhs_pad);
-
         // Greater or equal
         let expected_ge = str >= rhs;
 
@@ -509,6 +507,17 @@ impl Keys {
         result_message_rhs(str, rhs, expected_ge, dec_ge, end.duration_since(start));
         assert_eq!(dec_ge, expected_ge);
 
+        // Clear rhs
+        let start = Instant::now();
+        let result_ge = self.sk.ge(&enc_lhs, &clear_rhs);
+        let end = Instant::now();
+
+        let dec_ge = self.ck.key().decrypt_bool(&result_ge);
+
+        println!("\n\x1b[1mGe:\x1b[0m");
+        result_message_clear_rhs(str, rhs, expected_ge, dec_ge, end.duration_since(start));
+        assert_eq!(dec_ge, expected_ge);
+
         // Less or equal
         let expected_le = str <= rhs;
 
@@ -522,6 +531,17 @@ impl Keys {
         result_message_rhs(str, rhs, expected_le, dec_le, end.duration_since(start));
         assert_eq!(dec_le, expected_le);
 
+        // Clear rhs
+        let start = Instant::now();
+        let result_le = self.sk.le(&enc_lhs, &clear_rhs);
+        let end = Instant::now();
+
+        let dec_le = self.ck.key().decrypt_bool(&result_le);
+
+        println!("\n\x1b[1mLe:\x1b[0m");
+        result_message_clear_rhs(str, rhs, expected_le, dec_le, end.duration_since(start));
+        assert_eq!(dec_le, expected_le);
+
         // Greater than
         let expected_gt = str > rhs;
 
@@ -535,6 +555,17 @@ impl Keys {
         result_message_rhs(str, rhs, expected_gt, dec_gt, end.duration_since(start));
         assert_eq!(dec_gt, expected_gt);
 
+        // Clear rhs
+        let start = Instant::now();
+        let result_gt = self.sk.gt(&enc_lhs, &clear_rhs);
+        let end = Instant::now();
+
+        let dec_gt = self.ck.key().decrypt_bool(&result_gt);
+
+        println!("\n\x1b[1mGt:\x1b[0m");
+        result_message_clear_rhs(str, rhs, expected_gt, dec_gt, end.duration_since(start));
+        assert_eq!(dec_gt, expected_gt);
+
         // Less than
         let expected_lt = str < rhs;
 
@@ -547,6 +578,41 @@ impl Keys {
         println!("\n\x1b[1mLt:\x1b[0m");
         result_message_rhs(str, rhs, expected_lt, dec_lt, end.duration_since(start));
         assert_eq!(dec_lt, expected_lt);
+
+        // Clear rhs
+        let start = Instant::now();
+        let result_lt = self.sk.lt(&enc_lhs, &clear_rhs);
+        let end = Instant::now();
+
+        let dec_lt = self.ck.key().decrypt_bool(&result_lt);
+
+        println!("\n\x1b[1mLt:\x1b[0m");
+        result_message_clear_rhs(str, rhs, expected_lt, dec_lt, end.duration_since(start));
+        assert_eq!(dec_lt, expected_lt);
+
+        // Three-way comparison
+        let expected_cmp = str.cmp(rhs);
+
+        let start = Instant::now();
+        let result_cmp = self.sk.cmp(&enc_lhs, &enc_rhs);
+        let end = Instant::now();
+
+        let dec_cmp = self.ck.decrypt_ordering(&result_cmp);
+
+        println!("\n\x1b[1mCmp:\x1b[0m");
+        result_message_rhs(str, rhs, expected_cmp, dec_cmp, end.duration_since(start));
+        assert_eq!(dec_cmp, expected_cmp);
+
+        // Clear rhs
+        let start = Instant::now();
+        let result_cmp = self.sk.cmp(&enc_lhs, &clear_rhs);
+        let end = Instant::now();
+
+        let dec_cmp = self.ck.decrypt_ordering(&result_cmp);
+
+        println!("\n\x1b[1mCmp:\x1b[0m");
+        result_message_clear_rhs(str, rhs, expected_cmp, dec_cmp, end.duration_since(start));
+        assert_eq!(dec_cmp, expected_cmp);
     }
 
     pub fn assert_to_lowercase(&self, str: &str, str_pad: Option<u32>) {
@@ -806,6 +872,50 @@ impl Keys {
         assert_eq!(dec, expected);
     }
 
+    pub fn assert_split_at(&self, str: &str, str_pad: Option<u32>, mid: u16) {
+        let expected = (mid as usize <= str.len()).then(|| str.split_at(mid as usize));
+
+        let enc_str = FheString::new(&self.ck, str, str_pad);
+
+        for (enc_mid, kind) in [
+            (UIntArg::Clear(mid), "clear"),
+            (UIntArg::Enc(self.ck.encrypt_u16(mid, None)), "encrypted"),
+        ] {
+            let start = Instant::now();
+            let (lhs, rhs, in_bounds) = self.sk.split_at(&enc_str, &enc_mid);
+            let end = Instant::now();
+
+            let dec_lhs = self.ck.decrypt_ascii(&lhs);
+            let dec_rhs = self.ck.decrypt_ascii(&rhs);
+            let dec_in_bounds = self.ck.key().decrypt_bool(&in_bounds);
+
+            let dec = dec_in_bounds.then_some((dec_lhs.as_str(), dec_rhs.as_str()));
+
+            println!(
+                "\n\x1b[1mSplit_at:\x1b[0m\n\
+                \x1b[1;32m--------------------------------\x1b[0m\n\
+                \x1b[1;32;1mString: \x1b[0m\x1b[0;33m{:?}\x1b[0m\n\
+                \x1b[1;32;1mMid ({}): \x1b[0m{}\n\
+                \x1b[1;32;1mClear API Result: \x1b[0m{:?}\n\
+                \x1b[1;32;1mT-fhe API Result: \x1b[0m{:?}\n\
+                \x1b[1;34mExecution Time: \x1b[0m{:?}\n\
+                \x1b[1;32m--------------------------------\x1b[0m",
+                str,
+                kind,
+                mid,
+                expected,
+                dec,
+                end.duration_since(start),
+            );
+            assert_eq!(dec, expected);
+
+            // When `mid` is out of bounds it's clamped to the length
+            if !dec_in_bounds {
+                assert_eq!((dec_lhs.as_str(), dec_rhs.as_str()), (str, ""));
+            }
+        }
+    }
+
     pub fn assert_split(&self, str: &str, str_pad: Option<u32>, pat: &str, pat_pad: Option<u32>) {
         let mut expected: Vec<_> = str.split(pat).map(Some).collect();
         expected.push(None);
@@ -1228,6 +1338,122 @@ impl Keys {
         assert_eq!(dec_as_str, expected);
     }
 
+    pub fn assert_match_indices(
+        &self,
+        str: &str,
+        str_pad: Option<u32>,
+        pat: &str,
+        pat_pad: Option<u32>,
+    ) {
+        let mut expected: Vec<_> = str.match_indices(pat).map(|(i, _)| Some(i)).collect();
+        expected.push(None);
+
+        let enc_str = FheString::new(&self.ck, str, str_pad);
+        let enc_pat = GenericPattern::Enc(FheString::new(&self.ck, pat, pat_pad));
+
+        let mut results = Vec::with_capacity(expected.len());
+
+        // Call next enough times
+        let start = Instant::now();
+        let mut match_iter = self.sk.match_indices(&enc_str, &enc_pat);
+        for _ in 0..expected.len() {
+            results.push(match_iter.next(&self.sk))
+        }
+        let end = Instant::now();
+
+        let dec: Vec<_> = results
+            .iter()
+            .map(|(index, is_some)| {
+                let dec_is_some = self.ck.key().decrypt_bool(is_some);
+                let dec_index = self.ck.key().decrypt_radix::<u32>(index) as usize;
+
+                dec_is_some.then_some(dec_index)
+            })
+            .collect();
+
+        println!("\n\x1b[1mMatch_indices:\x1b[0m");
+        result_message_pat(str, pat, &expected, &dec, end.duration_since(start));
+
+        assert_eq!(dec, expected);
+    }
+
+    pub fn assert_rmatch_indices(
+        &self,
+        str: &str,
+        str_pad: Option<u32>,
+        pat: &str,
+        pat_pad: Option<u32>,
+    ) {
+        let mut expected: Vec<_> = str.rmatch_indices(pat).map(|(i, _)| Some(i)).collect();
+        expected.push(None);
+
+        let enc_str = FheString::new(&self.ck, str, str_pad);
+        let enc_pat = GenericPattern::Enc(FheString::new(&self.ck, pat, pat_pad));
+
+        let mut results = Vec::with_capacity(expected.len());
+
+        // Call next enough times
+        let start = Instant::now();
+        let mut rmatch_iter = self.sk.rmatch_indices(&enc_str, &enc_pat);
+        for _ in 0..expected.len() {
+            results.push(rmatch_iter.next(&self.sk))
+        }
+        let end = Instant::now();
+
+        let dec: Vec<_> = results
+            .iter()
+            .map(|(index, is_some)| {
+                let dec_is_some = self.ck.key().decrypt_bool(is_some);
+                let dec_index = self.ck.key().decrypt_radix::<u32>(index) as usize;
+
+                dec_is_some.then_some(dec_index)
+            })
+            .collect();
+
+        println!("\n\x1b[1mRmatch_indices:\x1b[0m");
+        result_message_pat(str, pat, &expected, &dec, end.duration_since(start));
+
+        assert_eq!(dec, expected);
+    }
+
+    pub fn assert_matches(&self, str: &str, str_pad: Option<u32>, pat: &str, pat_pad: Option<u32>) {
+        let mut expected: Vec<_> = str.matches(pat).map(Some).collect();
+        expected.push(None);
+
+        let enc_str = FheString::new(&self.ck, str, str_pad);
+        let enc_pat = GenericPattern::Enc(FheString::new(&self.ck, pat, pat_pad));
+
+        let mut results = Vec::with_capacity(expected.len());
+
+        // Call next enough times
+        let start = Instant::now();
+        let mut matches_iter = self.sk.matches(&enc_str, &enc_pat);
+        for _ in 0..expected.len() {
+            results.push(matches_iter.next(&self.sk))
+        }
+        let end = Instant::now();
+
+        // Collect the decrypted results properly
+        let dec: Vec<_> = results
+            .iter()
+            .map(|(result, is_some)| {
+                let dec_is_some = self.ck.key().decrypt_bool(is_some);
+
+                dec_is_some.then_some(self.ck.decrypt_ascii(result))
+            })
+            .collect();
+
+        let dec_as_str: Vec<_> = dec
+            .iter()
+            .map(|option| option.as_ref().map(|s| s.as_str()))
+            .collect();
+
+        println!("\n\x1b[1mMatches:\x1b[0m");
+        result_message_pat(str, pat, &expected, &dec_as_str, end.duration_since(start));
+
+        assert_eq!(dec_as_str, expected);
+    }
+
     pub fn assert_replace(
         &self,
         str: &str,
@@ -322,6 +322,17 @@ fn test_split_once() {
     }
 }
 
+#[test]
+fn test_split_at() {
+    let keys = Keys::new();
+
+    for (str, str_pad) in [("", 0), ("", 2), ("a", 0), ("foo", 1), ("foofoo", 3)] {
+        for mid in 0..=4 {
+            keys.assert_split_at(str, Some(str_pad), mid);
+        }
+    }
+}
+
 #[test]
 fn test_rsplit_real() {
     let keys = Keys::new();
@@ -340,6 +351,40 @@ fn test_split_real() {
     }
 }
 
+#[test]
+fn test_match_indices() {
+    let keys = Keys::new();
+
+    for ((str, str_pad), (pat, pat_pad)) in TEST_CASES_SPLIT {
+        keys.assert_match_indices(str, Some(str_pad), pat, Some(pat_pad));
+    }
+}
+
+#[test]
+fn test_rmatch_indices() {
+    let keys = Keys::new();
+
+    for ((str, str_pad), (pat, pat_pad)) in TEST_CASES_SPLIT {
+        keys.assert_rmatch_indices(str, Some(str_pad), pat, Some(pat_pad));
+    }
+
+    // The last match of an empty pattern is at index 0, where there's no char before it
+    for (str, str_pad) in [("a", 0), ("a", 1), ("ab", 2)] {
+        for pat_pad in 0..=1 {
+            keys.assert_rmatch_indices(str, Some(str_pad), "", Some(pat_pad));
+        }
+    }
+}
+
+#[test]
+fn test_matches() {
+    let keys = Keys::new();
+
+    for ((str, str_pad), (pat, pat_pad)) in TEST_CASES_SPLIT {
+        keys.assert_matches(str, Some(str_pad), pat, Some(pat_pad));
+    }
+}
+
 #[test]
 fn test_rsplitn() {
     let keys = Keys::new();
@@ -1,38 +1,79 @@
-use crate::client_key::{ClientKey, EncU16, EncryptOutput};
+use crate::client_key::{encrypt_padded, ClientKey, EncU16, EncryptOutput};
+use crate::error::{check_ascii, check_no_null, FheStringError};
+use crate::parameters::{num_blocks, CHAR_BITS};
 use crate::server_key::ServerKey;
 use crate::N;
+use rayon::prelude::*;
+use tfhe::integer::ciphertext::CompressedRadixCiphertext;
 use tfhe::integer::{IntegerCiphertext, IntegerRadixCiphertext, RadixCiphertext};
 
-/// Represents a encrypted ASCII character.
-#[derive(Clone)]
+/// Represents a encrypted ASCII character, or a single byte of an UTF-8 encoded character.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(into = "VersionedFheAsciiChar", from = "VersionedFheAsciiChar")]
 pub struct FheAsciiChar {
     enc_char: RadixCiphertext,
 }
 
-/// Represents a encrypted string made up of [`FheAsciiChar`]s.
-#[derive(Clone)]
+/// Represents a encrypted string made up of [`FheAsciiChar`]s. Non ASCII strings are stored as
+/// their UTF-8 bytes.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(into = "VersionedFheString", from = "VersionedFheString")]
 pub struct FheString {
     enc_string: Vec<FheAsciiChar>,
     padded: bool,
 }
 
+/// Represents a compressed [`FheString`], which is much smaller to store or send over the network.
+///
+/// It can only be encrypted with a [`ClientKey`], and has to be decompressed before operating on it.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(
+    into = "VersionedCompressedFheString",
+    from = "VersionedCompressedFheString"
+)]
+pub struct CompressedFheString {
+    enc_string: Vec<CompressedRadixCiphertext>,
+    padded: bool,
+}
+
 // For str functions that require unsigned integers as arguments
 pub enum UIntArg {
     Clear(u16),
     Enc(EncU16),
 }
 
-#[derive(Clone)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(into = "VersionedClearString", try_from = "VersionedClearString")]
 pub struct ClearString {
     str: String,
 }
 
 impl ClearString {
+    /// Constructs a new `ClearString` pattern.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the provided string contains null characters "\0" or is longer
+    /// than `N * 8` bytes. See [`ClearString::try_new`] for the fallible version.
     pub fn new(str: String) -> Self {
-        assert!(str.is_ascii() && !str.contains('\0'));
-        assert!(str.len() <= N * 8);
+        Self::try_new(str).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Constructs a new `ClearString` pattern.
+    ///
+    /// Returns an error if the provided string contains null characters "\0" or is longer than
+    /// `N * 8` bytes.
+    pub fn try_new(str: String) -> Result<Self, FheStringError> {
+        check_no_null(&str)?;
+
+        if str.len() > N * 8 {
+            return Err(FheStringError::PatternTooLong {
+                len: str.len(),
+                max: N * 8,
+            });
+        }
 
-        ClearString { str }
+        Ok(ClearString { str })
     }
 
     pub fn str(&self) -> &str {
@@ -40,7 +81,8 @@ impl ClearString {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(into = "VersionedGenericPattern", from = "VersionedGenericPattern")]
 pub enum GenericPattern {
     Clear(ClearString),
     Enc(FheString),
@@ -57,7 +99,7 @@ impl FheAsciiChar {
 
     pub fn null(sk: &ServerKey) -> Self {
         FheAsciiChar {
-            enc_char: sk.key().create_trivial_zero_radix(4),
+            enc_char: sk.key().create_trivial_zero_radix(sk.char_blocks()),
         }
     }
 }
@@ -70,29 +112,87 @@ impl FheString {
     ///
     /// # Panics
     ///
-    /// This function will panic if the provided string is not ASCII.
+    /// This function will panic if the provided string is not ASCII or contains null characters
+    /// "\0". See [`FheString::try_new`] for the fallible version.
     pub fn new(client_key: &ClientKey, str: &str, padding: Option<u32>) -> Self {
         let enc_output = client_key.encrypt_ascii(str, padding);
 
         FheString::from(enc_output)
     }
 
-    /// Constructs a trivial `FheString` from a plaintext string and a [`ServerKey`].
+    /// Constructs a new `FheString` from a plaintext string, a [`ClientKey`] and an optional
+    /// padding length.
+    ///
+    /// Utilizes [`ClientKey::try_encrypt_ascii`] for the encryption, returning an error if the
+    /// provided string is not ASCII or contains null characters "\0".
+    pub fn try_new(
+        client_key: &ClientKey,
+        str: &str,
+        padding: Option<u32>,
+    ) -> Result<Self, FheStringError> {
+        let enc_output = client_key.try_encrypt_ascii(str, padding)?;
+
+        Ok(FheString::from(enc_output))
+    }
+
+    /// Constructs a new `FheString` from a plaintext UTF-8 string, a [`ClientKey`] and an optional
+    /// padding length.
+    ///
+    /// Utilizes [`ClientKey::encrypt_utf8`] for the encryption.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the provided string contains null characters "\0".
+    pub fn new_utf8(client_key: &ClientKey, str: &str, padding: Option<u32>) -> Self {
+        let enc_output = client_key.encrypt_utf8(str, padding);
+
+        FheString::from(enc_output)
+    }
+
+    /// Constructs a new `FheString` from a plaintext UTF-8 string, a [`ClientKey`] and an optional
+    /// padding length.
+    ///
+    /// Utilizes [`ClientKey::try_encrypt_utf8`] for the encryption, returning an error if the
+    /// provided string contains null characters "\0".
+    pub fn try_new_utf8(
+        client_key: &ClientKey,
+        str: &str,
+        padding: Option<u32>,
+    ) -> Result<Self, FheStringError> {
+        let enc_output = client_key.try_encrypt_utf8(str, padding)?;
+
+        Ok(FheString::from(enc_output))
+    }
+
+    /// Constructs a trivial `FheString` from a plaintext UTF-8 string and a [`ServerKey`].
     ///
     /// ## WARNING:
     /// This only formats the value to fit the ciphertext. The result is NOT encrypted.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the provided string contains null characters "\0".
     pub fn trivial(server_key: &ServerKey, str: &str) -> Self {
+        Self::try_trivial(server_key, str).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Constructs a trivial `FheString` from a plaintext UTF-8 string and a [`ServerKey`], returning
+    /// an error if the provided string contains null characters "\0".
+    ///
+    /// ## WARNING:
+    /// This only formats the value to fit the ciphertext. The result is NOT encrypted.
+    pub fn try_trivial(server_key: &ServerKey, str: &str) -> Result<Self, FheStringError> {
         let trivial = server_key
-            .trivial_encrypt_ascii(str)
+            .try_trivial_encrypt_utf8(str)?
             .value()
             .into_iter()
             .map(|enc_char| FheAsciiChar { enc_char })
             .collect();
 
-        Self {
+        Ok(Self {
             enc_string: trivial,
             padded: false,
-        }
+        })
     }
 
     /// Constructs a new `FheString` from an [`EncryptOutput`], which is guaranteed to be correct.
@@ -128,18 +228,23 @@ impl FheString {
         self.padded = to;
     }
 
-    // Converts a `RadixCiphertext` to a `FheString`, building a `FheAsciiChar` for each 4 blocks.
-    // Panics if the uint doesn't have a number of blocks that is multiple of 4.
+    // Converts a `RadixCiphertext` to a `FheString`, building a `FheAsciiChar` for each group of
+    // blocks that encrypts a char (4 blocks with the default parameters, see `num_blocks`).
+    // Panics if the uint doesn't have a number of blocks that is multiple of the blocks per char.
     pub fn from_uint(uint: RadixCiphertext) -> FheString {
         let blocks_len = uint.blocks().len();
-        assert_eq!(blocks_len % 4, 0);
+        let char_blocks = uint
+            .blocks()
+            .first()
+            .map_or(1, |block| num_blocks(CHAR_BITS, block.message_modulus));
+        assert_eq!(blocks_len % char_blocks, 0);
 
         let mut ciphertexts = uint.into_blocks().into_iter().rev();
 
         let mut ascii_vec = vec![];
 
-        for _ in 0..blocks_len / 4 {
-            let mut byte_vec: Vec<_> = ciphertexts.by_ref().take(4).collect();
+        for _ in 0..blocks_len / char_blocks {
+            let mut byte_vec: Vec<_> = ciphertexts.by_ref().take(char_blocks).collect();
             byte_vec.reverse();
 
             let byte = RadixCiphertext::from_blocks(byte_vec);
@@ -154,7 +259,7 @@ impl FheString {
         }
     }
 
-    // Converts a `FheString` to a `RadixCiphertext`, taking 4 blocks for each `FheAsciiChar`.
+    // Converts a `FheString` to a `RadixCiphertext`, taking the blocks of each `FheAsciiChar`.
     // We can then use a single large uint, that represents a string, in tfhe-rs operations.
     pub fn to_uint(&self, sk: &ServerKey) -> RadixCiphertext {
         self.clone().into_uint(sk)
@@ -172,7 +277,7 @@ impl FheString {
 
         if uint.blocks().is_empty() {
             sk.key()
-                .extend_radix_with_trivial_zero_blocks_lsb_assign(&mut uint, 4);
+                .extend_radix_with_trivial_zero_blocks_lsb_assign(&mut uint, sk.char_blocks());
         }
 
         uint
@@ -196,11 +301,307 @@ impl FheString {
     }
 }
 
+impl CompressedFheString {
+    /// Constructs a new `CompressedFheString` from a plaintext string, a [`ClientKey`] and an
+    /// optional padding length.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the provided string is not ASCII or contains null characters
+    /// "\0". See [`CompressedFheString::try_new`] for the fallible version.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, compressed_sk) = gen_keys_compressed();
+    /// let sk = compressed_sk.decompress();
+    ///
+    /// let compressed = CompressedFheString::new(&ck, "hello", Some(2));
+    ///
+    /// let enc_s = compressed.decompress();
+    /// let result = sk.to_uppercase(&enc_s);
+    ///
+    /// assert_eq!(ck.decrypt_ascii(&result), "HELLO");
+    /// ```
+    pub fn new(client_key: &ClientKey, str: &str, padding: Option<u32>) -> Self {
+        Self::try_new(client_key, str, padding).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Constructs a new `CompressedFheString` from a plaintext string, a [`ClientKey`] and an
+    /// optional padding length.
+    ///
+    /// Returns an error if the provided string is not ASCII or contains null characters "\0".
+    pub fn try_new(
+        client_key: &ClientKey,
+        str: &str,
+        padding: Option<u32>,
+    ) -> Result<Self, FheStringError> {
+        check_ascii(str)?;
+
+        Ok(Self::encrypt_bytes(client_key, str.as_bytes(), padding))
+    }
+
+    /// Constructs a new `CompressedFheString` from a plaintext UTF-8 string, a [`ClientKey`] and
+    /// an optional padding length.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the provided string contains null characters "\0". See
+    /// [`CompressedFheString::try_new_utf8`] for the fallible version.
+    pub fn new_utf8(client_key: &ClientKey, str: &str, padding: Option<u32>) -> Self {
+        Self::try_new_utf8(client_key, str, padding).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Constructs a new `CompressedFheString` from a plaintext UTF-8 string, a [`ClientKey`] and
+    /// an optional padding length.
+    ///
+    /// Returns an error if the provided string contains null characters "\0".
+    pub fn try_new_utf8(
+        client_key: &ClientKey,
+        str: &str,
+        padding: Option<u32>,
+    ) -> Result<Self, FheStringError> {
+        check_no_null(str)?;
+
+        Ok(Self::encrypt_bytes(client_key, str.as_bytes(), padding))
+    }
+
+    fn encrypt_bytes(client_key: &ClientKey, bytes: &[u8], padding: Option<u32>) -> Self {
+        let blocks = client_key.char_blocks();
+        let encrypt = |byte: u8| client_key.key().encrypt_radix_compressed(byte, blocks);
+
+        let (enc_string, padded) = encrypt_padded(bytes, padding, encrypt);
+
+        Self { enc_string, padded }
+    }
+
+    /// Decompresses the string, returning a [`FheString`] with the same padding flag.
+    pub fn decompress(&self) -> FheString {
+        let enc_string = self
+            .enc_string
+            .par_iter()
+            .map(|compressed| FheAsciiChar {
+                enc_char: RadixCiphertext::from(compressed.clone()),
+            })
+            .collect();
+
+        FheString {
+            enc_string,
+            padded: self.padded,
+        }
+    }
+
+    pub fn is_padded(&self) -> bool {
+        self.padded
+    }
+}
+
+// The serialized representation of each type is wrapped in a versioned enum, with a variant for
+// each layout it has had. New layouts are added as new variants, so that values serialized by a
+// previous release can still be deserialized (and converted to the current layout).
+#[derive(serde::Serialize, serde::Deserialize)]
+enum VersionedFheAsciiChar {
+    V0(RadixCiphertext),
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+enum VersionedFheString {
+    V0(FheStringV0),
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct FheStringV0 {
+    enc_string: Vec<FheAsciiChar>,
+    padded: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+enum VersionedCompressedFheString {
+    V0(CompressedFheStringV0),
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CompressedFheStringV0 {
+    enc_string: Vec<CompressedRadixCiphertext>,
+    padded: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+enum VersionedClearString {
+    V0(String),
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+enum VersionedGenericPattern {
+    V0(GenericPatternV0),
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+enum GenericPatternV0 {
+    Clear(ClearString),
+    Enc(FheString),
+}
+
+impl From<FheAsciiChar> for VersionedFheAsciiChar {
+    fn from(char: FheAsciiChar) -> Self {
+        VersionedFheAsciiChar::V0(char.enc_char)
+    }
+}
+
+impl From<VersionedFheAsciiChar> for FheAsciiChar {
+    fn from(versioned: VersionedFheAsciiChar) -> Self {
+        match versioned {
+            VersionedFheAsciiChar::V0(enc_char) => FheAsciiChar { enc_char },
+        }
+    }
+}
+
+impl From<FheString> for VersionedFheString {
+    fn from(str: FheString) -> Self {
+        VersionedFheString::V0(FheStringV0 {
+            enc_string: str.enc_string,
+            padded: str.padded,
+        })
+    }
+}
+
+impl From<VersionedFheString> for FheString {
+    fn from(versioned: VersionedFheString) -> Self {
+        match versioned {
+            VersionedFheString::V0(FheStringV0 { enc_string, padded }) => {
+                FheString { enc_string, padded }
+            }
+        }
+    }
+}
+
+impl From<CompressedFheString> for VersionedCompressedFheString {
+    fn from(str: CompressedFheString) -> Self {
+        VersionedCompressedFheString::V0(CompressedFheStringV0 {
+            enc_string: str.enc_string,
+            padded: str.padded,
+        })
+    }
+}
+
+impl From<VersionedCompressedFheString> for CompressedFheString {
+    fn from(versioned: VersionedCompressedFheString) -> Self {
+        match versioned {
+            VersionedCompressedFheString::V0(CompressedFheStringV0 { enc_string, padded }) => {
+                CompressedFheString { enc_string, padded }
+            }
+        }
+    }
+}
+
+impl From<ClearString> for VersionedClearString {
+    fn from(str: ClearString) -> Self {
+        VersionedClearString::V0(str.str)
+    }
+}
+
+// The clear string is validated again, as it may come from an untrusted source
+impl TryFrom<VersionedClearString> for ClearString {
+    type Error = FheStringError;
+
+    fn try_from(versioned: VersionedClearString) -> Result<Self, Self::Error> {
+        match versioned {
+            VersionedClearString::V0(str) => ClearString::try_new(str),
+        }
+    }
+}
+
+impl From<GenericPattern> for VersionedGenericPattern {
+    fn from(pat: GenericPattern) -> Self {
+        let pat = match pat {
+            GenericPattern::Clear(pat) => GenericPatternV0::Clear(pat),
+            GenericPattern::Enc(pat) => GenericPatternV0::Enc(pat),
+        };
+
+        VersionedGenericPattern::V0(pat)
+    }
+}
+
+impl From<VersionedGenericPattern> for GenericPattern {
+    fn from(versioned: VersionedGenericPattern) -> Self {
+        let VersionedGenericPattern::V0(pat) = versioned;
+
+        match pat {
+            GenericPatternV0::Clear(pat) => GenericPattern::Clear(pat),
+            GenericPatternV0::Enc(pat) => GenericPattern::Enc(pat),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::server_key::gen_keys;
 
+    #[test]
+    fn test_clear_string_validation() {
+        assert!(ClearString::try_new("hello".to_string()).is_ok());
+        assert!(ClearString::try_new("ñandú".to_string()).is_ok());
+        assert_eq!(
+            ClearString::try_new("a\0".to_string()).err(),
+            Some(FheStringError::ContainsNull)
+        );
+        assert_eq!(
+            ClearString::try_new("a".repeat(N * 8 + 1)).err(),
+            Some(FheStringError::PatternTooLong {
+                len: N * 8 + 1,
+                max: N * 8,
+            })
+        );
+    }
+
+    #[test]
+    fn test_serialization() {
+        let (ck, _) = gen_keys();
+
+        let enc = FheString::new(&ck, "hello", Some(2));
+        let pat = GenericPattern::Clear(ClearString::new("world".to_string()));
+
+        let serialized = bincode::serialize(&(enc, pat)).unwrap();
+        let (enc, pat): (FheString, GenericPattern) = bincode::deserialize(&serialized).unwrap();
+
+        assert!(enc.is_padded());
+        assert_eq!(ck.decrypt_ascii(&enc), "hello");
+
+        match pat {
+            GenericPattern::Clear(pat) => assert_eq!(pat.str(), "world"),
+            GenericPattern::Enc(_) => panic!("Expected a clear pattern"),
+        }
+
+        // A serialized clear string that isn't valid cannot be deserialized
+        let invalid = VersionedClearString::V0("a\0".to_string());
+        let serialized = bincode::serialize(&invalid).unwrap();
+
+        assert!(bincode::deserialize::<ClearString>(&serialized).is_err());
+    }
+
+    #[test]
+    fn test_compressed_string() {
+        let (ck, _) = gen_keys();
+        let str = "Los Sheikah fueron originalmente criados de la Diosa Hylia.";
+
+        let enc = FheString::new(&ck, str, Some(3));
+        let compressed = CompressedFheString::new(&ck, str, Some(3));
+
+        let enc_size = bincode::serialize(&enc).unwrap().len();
+        let compressed_serialized = bincode::serialize(&compressed).unwrap();
+
+        // Compression should reduce the size at least by an order of magnitude
+        assert!(compressed_serialized.len() * 10 < enc_size);
+
+        let compressed: CompressedFheString = bincode::deserialize(&compressed_serialized).unwrap();
+        let decompressed = compressed.decompress();
+
+        assert!(decompressed.is_padded());
+        assert_eq!(ck.decrypt_ascii(&decompressed), str);
+    }
+
     #[test]
     fn test_uint_conversion() {
         let (ck, sk) = gen_keys();
@@ -1,6 +1,10 @@
 use crate::ciphertext::FheString;
+use crate::error::{check_ascii, check_no_null, FheStringError};
+use crate::parameters::{num_blocks, FheStringParameters, CHAR_BITS, U16_BITS};
+use crate::server_key::FheOrdering;
+use std::cmp::Ordering;
 use tfhe::integer::{ClientKey as FheClientKey, RadixCiphertext};
-use tfhe::shortint::prelude::PARAM_MESSAGE_2_CARRY_2;
+use tfhe::shortint::parameters::MessageModulus;
 
 /// Represents a client key for encryption and decryption of strings.
 #[derive(serde::Serialize, serde::Deserialize, Clone)]
@@ -9,12 +13,40 @@ pub struct ClientKey {
 }
 
 /// Encrypted u16 value. It contains an optional `max` to restrict the range of the value.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(into = "VersionedEncU16", from = "VersionedEncU16")]
 pub struct EncU16 {
     cipher: RadixCiphertext,
     max: Option<u16>,
 }
 
 impl EncU16 {
+    // Encrypts the value with the provided function, after checking that it doesn't exceed `max`
+    pub(crate) fn try_encrypt_with<F>(
+        val: u16,
+        max: Option<u16>,
+        encrypt: F,
+    ) -> Result<Self, FheStringError>
+    where
+        F: FnOnce(u16) -> RadixCiphertext,
+    {
+        if let Some(max_val) = max {
+            if val > max_val {
+                return Err(FheStringError::ValueExceedsMax { val, max: max_val });
+            }
+        }
+
+        Ok(EncU16 {
+            cipher: encrypt(val),
+            max,
+        })
+    }
+
+    // Wraps a ciphertext that is already known to encrypt a u16 value
+    pub(crate) fn from_cipher(cipher: RadixCiphertext, max: Option<u16>) -> Self {
+        EncU16 { cipher, max }
+    }
+
     pub fn cipher(&self) -> &RadixCiphertext {
         &self.cipher
     }
@@ -24,7 +56,8 @@ impl EncU16 {
     }
 }
 
-/// Output type returned by [`ClientKey::encrypt_ascii`].
+/// Output type returned by [`ClientKey::encrypt_ascii`] and
+/// [`PublicKey::encrypt_ascii`](crate::PublicKey::encrypt_ascii).
 ///
 /// It is used as an intermediate type to safely build a [`FheString`].
 pub struct EncryptOutput {
@@ -33,6 +66,16 @@ pub struct EncryptOutput {
 }
 
 impl EncryptOutput {
+    // Encrypts each byte with the provided function, see `encrypt_padded`
+    pub(crate) fn encrypt_with<F>(bytes: &[u8], padding: Option<u32>, encrypt: F) -> Self
+    where
+        F: Fn(u8) -> RadixCiphertext,
+    {
+        let (output, padded) = encrypt_padded(bytes, padding, encrypt);
+
+        EncryptOutput { output, padded }
+    }
+
     /// Extracts the value from the `EncryptOutput`.
     pub fn value(self) -> Vec<RadixCiphertext> {
         self.output
@@ -43,10 +86,43 @@ impl EncryptOutput {
     }
 }
 
+// Encrypts each byte with the provided function, optionally padding the result with the specified
+// amount of encrypted 0s. Also returns whether the result is padded
+pub(crate) fn encrypt_padded<T, F>(bytes: &[u8], padding: Option<u32>, encrypt: F) -> (Vec<T>, bool)
+where
+    F: Fn(u8) -> T,
+{
+    let padded = padding.map_or(false, |p| p != 0);
+
+    let mut enc_chars: Vec<_> = bytes.iter().map(|byte| encrypt(*byte)).collect();
+
+    // Optional padding
+    if let Some(count) = padding {
+        let null = (0..count).map(|_| encrypt(0u8));
+
+        enc_chars.extend(null);
+    }
+
+    (enc_chars, padded)
+}
+
+impl Default for ClientKey {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ClientKey {
+    /// Creates a client key with the default [`FheStringParameters`].
     pub fn new() -> Self {
+        Self::with_parameters(FheStringParameters::default())
+    }
+
+    /// Creates a client key with the provided [`FheStringParameters`], which determine the number
+    /// of blocks of every encrypted char, index and u16.
+    pub fn with_parameters(params: FheStringParameters) -> Self {
         Self {
-            key: FheClientKey::new(PARAM_MESSAGE_2_CARRY_2),
+            key: FheClientKey::new(params.shortint_parameters()),
         }
     }
 
@@ -54,34 +130,80 @@ impl ClientKey {
         &self.key
     }
 
+    pub(crate) fn message_modulus(&self) -> MessageModulus {
+        self.key.parameters().message_modulus()
+    }
+
+    pub(crate) fn char_blocks(&self) -> usize {
+        num_blocks(CHAR_BITS, self.message_modulus())
+    }
+
     /// Encrypts an ASCII string, optionally padding it with the specified amount of 0s, and returns
     /// an [`EncryptOutput`].
     ///
     /// # Panics
     ///
     /// This function will panic if the provided string is not ASCII or contains null characters
-    /// "\0".
+    /// "\0". See [`ClientKey::try_encrypt_ascii`] for the fallible version.
     pub fn encrypt_ascii(&self, str: &str, padding: Option<u32>) -> EncryptOutput {
-        assert!(str.is_ascii() & !str.contains('\0'));
+        self.try_encrypt_ascii(str, padding)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
 
-        let padded = padding.map_or(false, |p| p != 0);
+    /// Encrypts an ASCII string, optionally padding it with the specified amount of 0s, and returns
+    /// an [`EncryptOutput`].
+    ///
+    /// Returns an error if the provided string is not ASCII or contains null characters "\0".
+    pub fn try_encrypt_ascii(
+        &self,
+        str: &str,
+        padding: Option<u32>,
+    ) -> Result<EncryptOutput, FheStringError> {
+        check_ascii(str)?;
 
-        let mut enc_chars: Vec<_> = str
-            .bytes()
-            .map(|char| self.key.encrypt_radix(char, 4))
-            .collect();
+        let blocks = self.char_blocks();
+        let encrypt = |byte: u8| self.key.encrypt_radix(byte, blocks);
 
-        // Optional padding
-        if let Some(count) = padding {
-            let null = (0..count).map(|_| self.key.encrypt_radix(0u8, 4));
+        Ok(EncryptOutput::encrypt_with(
+            str.as_bytes(),
+            padding,
+            encrypt,
+        ))
+    }
 
-            enc_chars.extend(null);
-        }
+    /// Encrypts an UTF-8 string, optionally padding it with the specified amount of 0s, and returns
+    /// an [`EncryptOutput`]. Each byte of the UTF-8 encoding is encrypted as a
+    /// [`FheAsciiChar`](crate::FheAsciiChar).
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the provided string contains null characters "\0". See
+    /// [`ClientKey::try_encrypt_utf8`] for the fallible version.
+    pub fn encrypt_utf8(&self, str: &str, padding: Option<u32>) -> EncryptOutput {
+        self.try_encrypt_utf8(str, padding)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
 
-        EncryptOutput {
-            output: enc_chars,
-            padded,
-        }
+    /// Encrypts an UTF-8 string, optionally padding it with the specified amount of 0s, and returns
+    /// an [`EncryptOutput`]. Each byte of the UTF-8 encoding is encrypted as a
+    /// [`FheAsciiChar`](crate::FheAsciiChar).
+    ///
+    /// Returns an error if the provided string contains null characters "\0".
+    pub fn try_encrypt_utf8(
+        &self,
+        str: &str,
+        padding: Option<u32>,
+    ) -> Result<EncryptOutput, FheStringError> {
+        check_no_null(str)?;
+
+        let blocks = self.char_blocks();
+        let encrypt = |byte: u8| self.key.encrypt_radix(byte, blocks);
+
+        Ok(EncryptOutput::encrypt_with(
+            str.as_bytes(),
+            padding,
+            encrypt,
+        ))
     }
 
     /// Decrypts a `FheString`, removes any padding and returns the ASCII string.
@@ -89,43 +211,92 @@ impl ClientKey {
     /// # Panics
     ///
     /// This function will panic if the decrypted string is not ASCII or the `FheString` padding
-    /// flag doesn't match the actual string.
+    /// flag doesn't match the actual string. See [`ClientKey::try_decrypt_ascii`] for the fallible
+    /// version.
     pub fn decrypt_ascii(&self, enc_str: &FheString) -> String {
+        self.try_decrypt_ascii(enc_str)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Decrypts a `FheString`, removes any padding and returns the ASCII string.
+    ///
+    /// Returns an error if the decrypted string is not ASCII or the `FheString` padding flag
+    /// doesn't match the actual string.
+    pub fn try_decrypt_ascii(&self, enc_str: &FheString) -> Result<String, FheStringError> {
+        let bytes = self.decrypt_bytes(enc_str)?;
+
+        if !bytes.is_ascii() {
+            return Err(FheStringError::NonAscii);
+        }
+
+        // Every byte is ASCII, hence valid UTF-8
+        Ok(String::from_utf8(bytes).unwrap())
+    }
+
+    /// Decrypts a `FheString`, removes any padding and returns the UTF-8 string.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the decrypted bytes are not valid UTF-8 or the `FheString`
+    /// padding flag doesn't match the actual string. See [`ClientKey::try_decrypt_utf8`] for the
+    /// fallible version.
+    pub fn decrypt_utf8(&self, enc_str: &FheString) -> String {
+        self.try_decrypt_utf8(enc_str)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Decrypts a `FheString`, removes any padding and returns the UTF-8 string.
+    ///
+    /// Returns an error if the decrypted bytes are not valid UTF-8 or the `FheString` padding flag
+    /// doesn't match the actual string.
+    pub fn try_decrypt_utf8(&self, enc_str: &FheString) -> Result<String, FheStringError> {
+        let bytes = self.decrypt_bytes(enc_str)?;
+
+        String::from_utf8(bytes).map_err(|_| FheStringError::InvalidUtf8)
+    }
+
+    /// Decrypts a `FheOrdering`, as returned by [`ServerKey::cmp`](crate::ServerKey::cmp).
+    pub fn decrypt_ordering(&self, ordering: &FheOrdering) -> Ordering {
+        let is_lt = self.key.decrypt_bool(ordering.is_lt());
+        let is_gt = self.key.decrypt_bool(ordering.is_gt());
+
+        match (is_lt, is_gt) {
+            (true, _) => Ordering::Less,
+            (_, true) => Ordering::Greater,
+            _ => Ordering::Equal,
+        }
+    }
+
+    // Decrypts the non null bytes of a `FheString`, checking that nulls are only at the end and
+    // that they match the padding flag
+    fn decrypt_bytes(&self, enc_str: &FheString) -> Result<Vec<u8>, FheStringError> {
         let padded_flag = enc_str.is_padded();
         let mut prev_was_null = false;
+        let mut bytes = Vec::with_capacity(enc_str.chars().len());
 
-        let bytes: Vec<_> = enc_str
-            .chars()
-            .iter()
-            .filter_map(|enc_char| {
-                let byte = self.key.decrypt_radix(enc_char.ciphertext());
+        for enc_char in enc_str.chars() {
+            let byte: u8 = self.key.decrypt_radix(enc_char.ciphertext());
 
-                if byte == 0 {
-                    prev_was_null = true;
+            if byte == 0 {
+                prev_was_null = true;
 
-                    assert!(padded_flag, "NULL FOUND BUT PADDED FLAG WAS FALSE");
-                } else {
-                    assert!(!prev_was_null, "NON ZERO CHAR AFTER A NULL");
-
-                    prev_was_null = false;
+                if !padded_flag {
+                    return Err(FheStringError::NullWithoutPadding);
                 }
-
-                if byte != 0 {
-                    Some(byte)
-                } else {
-                    None
+            } else {
+                if prev_was_null {
+                    return Err(FheStringError::CharAfterNull);
                 }
-            })
-            .collect();
-
-        if padded_flag {
-            assert!(
-                prev_was_null,
-                "LAST CHAR WAS NOT NULL BUT PADDING FLAG WAS SET"
-            )
+
+                bytes.push(byte);
+            }
+        }
+
+        if padded_flag && !prev_was_null {
+            return Err(FheStringError::MissingNullPadding);
         }
 
-        String::from_utf8(bytes).unwrap()
+        Ok(bytes)
     }
 
     /// Encrypts a u16 value. It also takes an optional `max` value to restrict the range
@@ -133,15 +304,120 @@ impl ClientKey {
     ///
     /// # Panics
     ///
-    /// This function will panic if the u16 value exceeds the provided `max`.
+    /// This function will panic if the u16 value exceeds the provided `max`. See
+    /// [`ClientKey::try_encrypt_u16`] for the fallible version.
     pub fn encrypt_u16(&self, val: u16, max: Option<u16>) -> EncU16 {
-        if let Some(max_val) = max {
-            assert!(val <= max_val, "val cannot be greater than max")
-        }
+        self.try_encrypt_u16(val, max)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
 
-        EncU16 {
-            cipher: self.key.encrypt_radix(val, 8),
-            max,
+    /// Encrypts a u16 value. It also takes an optional `max` value to restrict the range
+    /// of the encrypted u16.
+    ///
+    /// Returns an error if the u16 value exceeds the provided `max`.
+    pub fn try_encrypt_u16(&self, val: u16, max: Option<u16>) -> Result<EncU16, FheStringError> {
+        let blocks = num_blocks(U16_BITS, self.message_modulus());
+
+        EncU16::try_encrypt_with(val, max, |val| self.key.encrypt_radix(val, blocks))
+    }
+}
+
+// Versioned serialized representation of `EncU16`, see the ones in `ciphertext.rs`
+#[derive(serde::Serialize, serde::Deserialize)]
+enum VersionedEncU16 {
+    V0(EncU16V0),
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct EncU16V0 {
+    cipher: RadixCiphertext,
+    max: Option<u16>,
+}
+
+impl From<EncU16> for VersionedEncU16 {
+    fn from(enc: EncU16) -> Self {
+        VersionedEncU16::V0(EncU16V0 {
+            cipher: enc.cipher,
+            max: enc.max,
+        })
+    }
+}
+
+impl From<VersionedEncU16> for EncU16 {
+    fn from(versioned: VersionedEncU16) -> Self {
+        match versioned {
+            VersionedEncU16::V0(EncU16V0 { cipher, max }) => EncU16 { cipher, max },
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server_key::gen_keys;
+
+    #[test]
+    fn test_fallible_encryption() {
+        let (ck, _) = gen_keys();
+
+        assert_eq!(
+            ck.try_encrypt_ascii("héllo", None).err(),
+            Some(FheStringError::NonAscii)
+        );
+        assert_eq!(
+            ck.try_encrypt_ascii("he\0llo", None).err(),
+            Some(FheStringError::ContainsNull)
+        );
+        assert_eq!(
+            ck.try_encrypt_u16(3, Some(2)).err(),
+            Some(FheStringError::ValueExceedsMax { val: 3, max: 2 })
+        );
+        assert!(ck.try_encrypt_u16(2, Some(2)).is_ok());
+    }
+
+    #[test]
+    fn test_enc_u16_serialization() {
+        let (ck, _) = gen_keys();
+
+        let enc = ck.encrypt_u16(7, Some(10));
+
+        let serialized = bincode::serialize(&enc).unwrap();
+        let enc: EncU16 = bincode::deserialize(&serialized).unwrap();
+
+        assert_eq!(enc.max(), Some(10));
+        assert_eq!(ck.key().decrypt_radix::<u16>(enc.cipher()), 7);
+    }
+
+    #[test]
+    fn test_fallible_decryption() {
+        let (ck, _) = gen_keys();
+
+        let mut enc = FheString::new(&ck, "hello", Some(2));
+        assert_eq!(ck.try_decrypt_ascii(&enc), Ok("hello".to_string()));
+
+        enc.set_is_padded(false);
+        assert_eq!(
+            ck.try_decrypt_ascii(&enc),
+            Err(FheStringError::NullWithoutPadding)
+        );
+
+        let mut enc = FheString::new(&ck, "hello", None);
+        enc.set_is_padded(true);
+        assert_eq!(
+            ck.try_decrypt_ascii(&enc),
+            Err(FheStringError::MissingNullPadding)
+        );
+    }
+
+    #[test]
+    fn test_utf8_encryption() {
+        let (ck, _) = gen_keys();
+
+        let str = "Ñandú 東京";
+        let enc = FheString::new_utf8(&ck, str, Some(3));
+
+        assert_eq!(enc.chars().len(), str.len() + 3);
+        assert_eq!(ck.decrypt_utf8(&enc), str);
+        assert_eq!(ck.try_decrypt_ascii(&enc), Err(FheStringError::NonAscii));
+    }
+}
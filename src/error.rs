@@ -0,0 +1,89 @@
+use std::error::Error;
+use std::fmt;
+
+/// Errors returned by the fallible (`try_`) functions of the library, caused by malformed input or
+/// by an encrypted string that doesn't match its padding flag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FheStringError {
+    /// The string contains non ASCII characters.
+    NonAscii,
+    /// The decrypted bytes are not valid UTF-8.
+    InvalidUtf8,
+    /// The string contains null characters "\0".
+    ContainsNull,
+    /// The clear pattern is longer than the maximum length supported by a `ClearString`.
+    PatternTooLong { len: usize, max: usize },
+    /// The u16 value is greater than the provided `max`.
+    ValueExceedsMax { val: u16, max: u16 },
+    /// A null character was decrypted but the padding flag was not set.
+    NullWithoutPadding,
+    /// A non null character was decrypted after a null one.
+    CharAfterNull,
+    /// The padding flag was set but the last decrypted character was not null.
+    MissingNullPadding,
+    /// The clear regex is malformed or uses an unsupported feature, found at the given char index.
+    InvalidRegex { index: usize, reason: &'static str },
+    /// The format template is malformed, found at the given byte index.
+    InvalidTemplate { index: usize, reason: &'static str },
+    /// The number of `{}` placeholders in the format template is different from the number of
+    /// arguments.
+    FormatArgsMismatch { placeholders: usize, args: usize },
+}
+
+impl fmt::Display for FheStringError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FheStringError::NonAscii => write!(f, "string contains non ASCII characters"),
+            FheStringError::InvalidUtf8 => write!(f, "decrypted bytes are not valid UTF-8"),
+            FheStringError::ContainsNull => write!(f, "string contains null characters"),
+            FheStringError::PatternTooLong { len, max } => {
+                write!(
+                    f,
+                    "clear pattern length ({len}) exceeds the maximum length ({max})"
+                )
+            }
+            FheStringError::ValueExceedsMax { val, max } => {
+                write!(f, "val ({val}) cannot be greater than max ({max})")
+            }
+            FheStringError::NullWithoutPadding => write!(f, "null found but padded flag was false"),
+            FheStringError::CharAfterNull => write!(f, "non zero char after a null"),
+            FheStringError::MissingNullPadding => {
+                write!(f, "last char was not null but padding flag was set")
+            }
+            FheStringError::InvalidRegex { index, reason } => {
+                write!(f, "invalid regex at index {index}: {reason}")
+            }
+            FheStringError::InvalidTemplate { index, reason } => {
+                write!(f, "invalid format template at index {index}: {reason}")
+            }
+            FheStringError::FormatArgsMismatch { placeholders, args } => {
+                write!(
+                    f,
+                    "template has {placeholders} placeholders but {args} args were given"
+                )
+            }
+        }
+    }
+}
+
+impl Error for FheStringError {}
+
+/// Checks that a clear string can be encrypted as a `FheString`, i.e. it's ASCII and doesn't
+/// contain null characters.
+pub(crate) fn check_ascii(str: &str) -> Result<(), FheStringError> {
+    if !str.is_ascii() {
+        return Err(FheStringError::NonAscii);
+    }
+
+    check_no_null(str)
+}
+
+/// Checks that a clear UTF-8 string can be encrypted as a `FheString`, i.e. it doesn't contain
+/// null characters.
+pub(crate) fn check_no_null(str: &str) -> Result<(), FheStringError> {
+    if str.contains('\0') {
+        return Err(FheStringError::ContainsNull);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,27 @@
+mod ciphertext;
+mod client_key;
+mod error;
+mod parameters;
+mod public_key;
+mod regex;
+mod server_key;
+
+pub use ciphertext::{
+    ClearString, CompressedFheString, FheAsciiChar, FheString, GenericPattern, UIntArg,
+};
+pub use client_key::{ClientKey, EncU16, EncryptOutput};
+pub use error::FheStringError;
+pub use parameters::FheStringParameters;
+pub use public_key::PublicKey;
+pub use regex::ClearRegex;
+pub use server_key::{
+    gen_keys, gen_keys_compressed, gen_keys_compressed_with_parameters, gen_keys_with_parameters,
+    CompressedServerKey, FheIndexIterator, FheOrdering, FheStringCharLen, FheStringIsEmpty,
+    FheStringIterator, FheStringLen, LengthBucket, MatchIndices, Matches, RMatchIndices, RSplit,
+    RSplitN, RSplitTerminator, ServerKey, Split, SplitAsciiWhitespace, SplitInclusive, SplitN,
+    SplitTerminator, TrivialEncryptOutput,
+};
+
+/// Used as the const argument for `StaticUnsignedBigInt`, specifying the max u64 length of a
+/// [`ClearString`]. With `N = 4` clear patterns can be up to 32 characters long.
+pub const N: usize = 4;
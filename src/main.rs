@@ -1,19 +1,12 @@
-use crate::ciphertext::{FheString, UIntArg};
-use crate::client_key::ClientKey;
-use crate::server_key::{gen_keys, FheStringIsEmpty, FheStringIterator, FheStringLen, ServerKey};
 use clap::{value_parser, Arg, Command};
+use fhe_strings::{
+    gen_keys, ClientKey, FheIndexIterator, FheString, FheStringCharLen, FheStringIsEmpty,
+    FheStringIterator, ServerKey, UIntArg,
+};
 use std::time::Instant;
 
-mod ciphertext;
-mod client_key;
-mod server_key;
-
 mod assert_functions;
 
-// Used as the const argument for StaticUnsignedBigInt, specifying the max u64 length of a
-// ClearString
-const N: usize = 4;
-
 fn main() {
     let matches = Command::new("FHE str API")
         .arg(
@@ -131,6 +124,7 @@ fn main() {
 
     keys.assert_split_once(str, str_pad, pat, pat_pad);
     keys.assert_rsplit_once(str, str_pad, pat, pat_pad);
+    keys.assert_split_at(str, str_pad, n);
 
     keys.assert_split(str, str_pad, pat, pat_pad);
     keys.assert_rsplit(str, str_pad, pat, pat_pad);
@@ -142,6 +136,10 @@ fn main() {
     keys.assert_splitn(str, str_pad, pat, pat_pad, n, max);
     keys.assert_rsplitn(str, str_pad, pat, pat_pad, n, max);
 
+    keys.assert_match_indices(str, str_pad, pat, pat_pad);
+    keys.assert_rmatch_indices(str, str_pad, pat, pat_pad);
+    keys.assert_matches(str, str_pad, pat, pat_pad);
+
     keys.assert_replace(str, str_pad, pat, pat_pad, to, to_pad);
     keys.assert_replacen((str, str_pad), (pat, pat_pad), (to, to_pad), n, max);
 }
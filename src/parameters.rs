@@ -0,0 +1,117 @@
+use tfhe::shortint::parameters::{
+    ClassicPBSParameters, MessageModulus, PARAM_MESSAGE_1_CARRY_1, PARAM_MESSAGE_2_CARRY_2,
+    PARAM_MESSAGE_3_CARRY_3,
+};
+
+// Number of bits of the encrypted values: each char is a byte, indices and lengths are u32s and
+// the `n` arguments are u16s
+pub(crate) const CHAR_BITS: usize = 8;
+pub(crate) const INDEX_BITS: usize = 32;
+pub(crate) const U16_BITS: usize = 16;
+
+/// Cryptographic parameter sets that can be used to generate the keys, see
+/// [`gen_keys_with_parameters`](crate::gen_keys_with_parameters).
+///
+/// Smaller message and carry sizes result in smaller keys, but require more blocks per char (and
+/// hence more operations), while bigger sizes have the opposite trade-off.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum FheStringParameters {
+    /// 1 bit of message and 1 bit of carry per block, i.e. 8 blocks per char.
+    Message1Carry1,
+    /// 2 bits of message and 2 bits of carry per block, i.e. 4 blocks per char.
+    #[default]
+    Message2Carry2,
+    /// 3 bits of message and 3 bits of carry per block, i.e. 3 blocks per char.
+    Message3Carry3,
+}
+
+impl FheStringParameters {
+    pub fn shortint_parameters(self) -> ClassicPBSParameters {
+        match self {
+            FheStringParameters::Message1Carry1 => PARAM_MESSAGE_1_CARRY_1,
+            FheStringParameters::Message2Carry2 => PARAM_MESSAGE_2_CARRY_2,
+            FheStringParameters::Message3Carry3 => PARAM_MESSAGE_3_CARRY_3,
+        }
+    }
+
+    /// Number of blocks of each encrypted char.
+    pub fn blocks_per_char(self) -> usize {
+        num_blocks(CHAR_BITS, self.shortint_parameters().message_modulus)
+    }
+
+    /// Number of blocks of the encrypted indices and lengths returned by the string functions.
+    pub fn blocks_per_index(self) -> usize {
+        num_blocks(INDEX_BITS, self.shortint_parameters().message_modulus)
+    }
+}
+
+// Number of blocks needed to encrypt a value of `bits` bits, given the message modulus of the
+// blocks. If the message bits don't divide `bits`, the most significant block is not fully used
+pub(crate) fn num_blocks(bits: usize, message_modulus: MessageModulus) -> usize {
+    let message_bits = message_modulus.0.ilog2() as usize;
+
+    bits.div_ceil(message_bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ciphertext::{ClearString, FheString, GenericPattern, UIntArg};
+    use crate::server_key::{gen_keys_with_parameters, FheStringIterator};
+
+    #[test]
+    fn test_blocks() {
+        let blocks: Vec<_> = [
+            FheStringParameters::Message1Carry1,
+            FheStringParameters::Message2Carry2,
+            FheStringParameters::Message3Carry3,
+        ]
+        .into_iter()
+        .map(|params| (params.blocks_per_char(), params.blocks_per_index()))
+        .collect();
+
+        assert_eq!(blocks, [(8, 32), (4, 16), (3, 11)]);
+    }
+
+    #[test]
+    fn test_other_parameters() {
+        for params in [
+            FheStringParameters::Message1Carry1,
+            FheStringParameters::Message3Carry3,
+        ] {
+            let (ck, sk) = gen_keys_with_parameters(params);
+
+            let enc_s = FheString::new(&ck, "Hello world", Some(2));
+            let clear_pat = GenericPattern::Clear(ClearString::new("o".to_string()));
+            let enc_pat = GenericPattern::Enc(FheString::new(&ck, "wor", Some(1)));
+
+            let (index, is_some) = sk.rfind(&enc_s, &clear_pat);
+            assert!(ck.key().decrypt_bool(&is_some));
+            assert_eq!(ck.key().decrypt_radix::<u32>(&index), 7);
+
+            let (index, is_some) = sk.find(&enc_s, &enc_pat);
+            assert!(ck.key().decrypt_bool(&is_some));
+            assert_eq!(ck.key().decrypt_radix::<u32>(&index), 6);
+
+            let enc_to = FheString::new(&ck, "0", None);
+            let enc_n = UIntArg::Enc(ck.encrypt_u16(1, Some(2)));
+            let replaced = sk.replacen(&enc_s, &clear_pat, &enc_to, &enc_n);
+            assert_eq!(ck.decrypt_ascii(&replaced), "Hell0 world");
+
+            let mut split = sk.split(&sk.to_uppercase(&enc_s), &enc_pat);
+            let (first, _) = split.next(&sk);
+            assert_eq!(ck.decrypt_ascii(&first), "HELLO WORLD");
+
+            let (first, _) = sk.split(&enc_s, &enc_pat).next(&sk);
+            assert_eq!(ck.decrypt_ascii(&first), "Hello ");
+
+            let mut words = sk.split_ascii_whitespace(&enc_s);
+            let (first, _) = words.next(&sk);
+            let (second, _) = words.next(&sk);
+            let (_, no_more_items) = words.next(&sk);
+            assert_eq!(ck.decrypt_ascii(&first), "Hello");
+            assert_eq!(ck.decrypt_ascii(&second), "world");
+            assert!(!ck.key().decrypt_bool(&no_more_items));
+        }
+    }
+}
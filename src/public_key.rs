@@ -0,0 +1,187 @@
+use crate::client_key::{ClientKey, EncU16, EncryptOutput};
+use crate::error::{check_ascii, check_no_null, FheStringError};
+use crate::parameters::{num_blocks, CHAR_BITS, U16_BITS};
+use tfhe::integer::PublicKey as FhePublicKey;
+use tfhe::shortint::parameters::MessageModulus;
+
+/// Represents a public key for encryption of strings, derived from a [`ClientKey`].
+///
+/// It allows any party to encrypt [`FheString`](crate::FheString)s and [`EncU16`]s that can be
+/// used with the [`ServerKey`](crate::ServerKey) methods, while only the `ClientKey` owner can
+/// decrypt them. Clear patterns ([`ClearString`](crate::ClearString)) require no key at all.
+///
+/// It's built on the classic integer public key rather than on a compact one, as compact public
+/// key encryption is only supported with dedicated parameter sets (or with an extra key switch to
+/// the compute parameters), which don't exist for every
+/// [`FheStringParameters`](crate::FheStringParameters). The cost is that the key is made of many
+/// encryptions of zero, so it's much larger than the `ClientKey` and should be distributed once
+/// rather than per request, and that every block of every char is a separate public key
+/// encryption. To reduce the bandwidth of the ciphertexts themselves, see
+/// [`CompressedFheString`](crate::CompressedFheString), which requires the `ClientKey`.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct PublicKey {
+    key: FhePublicKey,
+    // Needed to know how many blocks to encrypt, as it depends on the client key parameters
+    message_modulus: MessageModulus,
+}
+
+impl PublicKey {
+    pub fn new(from: &ClientKey) -> Self {
+        Self {
+            key: FhePublicKey::new(from.key()),
+            message_modulus: from.message_modulus(),
+        }
+    }
+
+    pub fn key(&self) -> &FhePublicKey {
+        &self.key
+    }
+
+    /// Encrypts an ASCII string, optionally padding it with the specified amount of 0s, and returns
+    /// an [`EncryptOutput`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the provided string is not ASCII or contains null characters
+    /// "\0". See [`PublicKey::try_encrypt_ascii`] for the fallible version.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    /// let pk = PublicKey::new(&ck);
+    ///
+    /// let enc_s = FheString::from(pk.encrypt_ascii("hello", Some(2)));
+    /// let pat = GenericPattern::Clear(ClearString::new("ell".to_string()));
+    ///
+    /// let result = sk.contains(&enc_s, &pat);
+    /// let contains = ck.key().decrypt_bool(&result);
+    ///
+    /// assert_eq!(ck.decrypt_ascii(&enc_s), "hello");
+    /// assert!(contains);
+    /// ```
+    pub fn encrypt_ascii(&self, str: &str, padding: Option<u32>) -> EncryptOutput {
+        self.try_encrypt_ascii(str, padding)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Encrypts an ASCII string, optionally padding it with the specified amount of 0s, and returns
+    /// an [`EncryptOutput`].
+    ///
+    /// Returns an error if the provided string is not ASCII or contains null characters "\0".
+    pub fn try_encrypt_ascii(
+        &self,
+        str: &str,
+        padding: Option<u32>,
+    ) -> Result<EncryptOutput, FheStringError> {
+        check_ascii(str)?;
+
+        let blocks = num_blocks(CHAR_BITS, self.message_modulus);
+        let encrypt = |byte: u8| self.key.encrypt_radix(byte, blocks);
+
+        Ok(EncryptOutput::encrypt_with(
+            str.as_bytes(),
+            padding,
+            encrypt,
+        ))
+    }
+
+    /// Encrypts an UTF-8 string, optionally padding it with the specified amount of 0s, and returns
+    /// an [`EncryptOutput`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the provided string contains null characters "\0". See
+    /// [`PublicKey::try_encrypt_utf8`] for the fallible version.
+    pub fn encrypt_utf8(&self, str: &str, padding: Option<u32>) -> EncryptOutput {
+        self.try_encrypt_utf8(str, padding)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Encrypts an UTF-8 string, optionally padding it with the specified amount of 0s, and returns
+    /// an [`EncryptOutput`].
+    ///
+    /// Returns an error if the provided string contains null characters "\0".
+    pub fn try_encrypt_utf8(
+        &self,
+        str: &str,
+        padding: Option<u32>,
+    ) -> Result<EncryptOutput, FheStringError> {
+        check_no_null(str)?;
+
+        let blocks = num_blocks(CHAR_BITS, self.message_modulus);
+        let encrypt = |byte: u8| self.key.encrypt_radix(byte, blocks);
+
+        Ok(EncryptOutput::encrypt_with(
+            str.as_bytes(),
+            padding,
+            encrypt,
+        ))
+    }
+
+    /// Encrypts a u16 value. It also takes an optional `max` value to restrict the range
+    /// of the encrypted u16.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the u16 value exceeds the provided `max`. See
+    /// [`PublicKey::try_encrypt_u16`] for the fallible version.
+    pub fn encrypt_u16(&self, val: u16, max: Option<u16>) -> EncU16 {
+        self.try_encrypt_u16(val, max)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Encrypts a u16 value. It also takes an optional `max` value to restrict the range
+    /// of the encrypted u16.
+    ///
+    /// Returns an error if the u16 value exceeds the provided `max`.
+    pub fn try_encrypt_u16(&self, val: u16, max: Option<u16>) -> Result<EncU16, FheStringError> {
+        let blocks = num_blocks(U16_BITS, self.message_modulus);
+
+        EncU16::try_encrypt_with(val, max, |val| self.key.encrypt_radix(val, blocks))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ciphertext::{FheString, UIntArg};
+    use crate::server_key::gen_keys;
+
+    #[test]
+    fn test_public_key_encryption() {
+        let (ck, sk) = gen_keys();
+        let pk = PublicKey::new(&ck);
+
+        for (str, pad) in [("", 0), ("", 2), ("hello", 0), ("hello", 3), ("Ñandú", 1)] {
+            let enc_s = FheString::from(pk.encrypt_utf8(str, Some(pad)));
+
+            assert_eq!(enc_s.is_padded(), pad != 0);
+            assert_eq!(enc_s.chars().len(), str.len() + pad as usize);
+            assert_eq!(ck.decrypt_utf8(&enc_s), str);
+        }
+
+        for (val, max) in [(0, None), (7, Some(7)), (300, Some(1000)), (u16::MAX, None)] {
+            let enc_n = pk.encrypt_u16(val, max);
+
+            assert_eq!(enc_n.max(), max);
+            assert_eq!(ck.key().decrypt_radix::<u16>(enc_n.cipher()), val);
+        }
+
+        let enc_s = FheString::from(pk.encrypt_utf8("Ñandú", Some(1)));
+        let enc_n = pk.encrypt_u16(2, Some(3));
+
+        let result = sk.repeat(&enc_s, &UIntArg::Enc(enc_n));
+
+        assert_eq!(ck.decrypt_utf8(&result), "ÑandúÑandú");
+        assert_eq!(
+            pk.try_encrypt_ascii("ñ", None).err(),
+            Some(FheStringError::NonAscii)
+        );
+        assert_eq!(
+            pk.try_encrypt_u16(4, Some(3)).err(),
+            Some(FheStringError::ValueExceedsMax { val: 4, max: 3 })
+        );
+    }
+}
@@ -0,0 +1,674 @@
+use crate::error::{check_no_null, FheStringError};
+
+// Counted repetitions are expanded in the automaton, so we bound them to keep it small
+const MAX_REPETITIONS: u32 = 255;
+
+// Nested repetitions multiply the size of the automaton, so we also bound the total number of nodes
+const MAX_NODES: u64 = 10_000;
+
+// The ast is parsed, compiled and dropped recursively, so we bound its depth to avoid overflowing
+// the stack with deeply nested groups or repetitions
+const MAX_DEPTH: usize = 128;
+
+/// A clear regular expression, compiled to an automaton that can be evaluated over an encrypted
+/// string with [`ServerKey::regex_is_match`](crate::ServerKey::regex_is_match).
+///
+/// The supported syntax is a subset of the one of the `regex` crate:
+/// - Literal chars, and `\` to escape any ASCII punctuation char (plus `\n`, `\t`, `\r`, `\f` and
+///   `\v`)
+/// - `.`, which matches any char except `\n`
+/// - Character classes like `[a-z0-9_]` or `[^,;]` (where a `]` right after the `[` or `[^` is a
+///   literal), and `\d`, `\w`, `\s` (or `\D`, `\W`, `\S` for their negations)
+/// - The `?`, `*`, `+`, `{n}`, `{n,}` and `{n,m}` quantifiers
+/// - Groups with `(...)` or `(?:...)`, and alternation with `|`
+/// - The `^` and `$` anchors
+///
+/// Like `Regex::is_match`, a match can be found anywhere in the string unless the regex is
+/// anchored. Character classes can only contain ASCII chars, while `.` and negated classes also
+/// match any non ASCII char.
+#[derive(Clone, Debug)]
+pub struct ClearRegex {
+    regex: String,
+    nodes: Vec<RegexNode>,
+    // Each class is a sorted list of non overlapping byte ranges, which never contain the null byte
+    classes: Vec<Vec<(u8, u8)>>,
+    start: usize,
+}
+
+// Nodes of the automaton, where `next` is the index of the following node
+#[derive(Clone, Debug)]
+pub(crate) enum RegexNode {
+    Match,
+    // Consumes a byte in the class
+    Class { class: usize, next: usize },
+    Split(Vec<usize>),
+    // Anchors, which don't consume bytes
+    Start(usize),
+    End(usize),
+}
+
+enum Ast {
+    Empty,
+    Class(Vec<(u8, u8)>),
+    Start,
+    End,
+    Concat(Vec<Ast>),
+    Alt(Vec<Ast>),
+    Repeat {
+        ast: Box<Ast>,
+        min: u32,
+        max: Option<u32>,
+    },
+}
+
+impl ClearRegex {
+    /// Constructs a new `ClearRegex`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the provided regex is malformed, uses an unsupported feature or
+    /// contains null characters "\0". See [`ClearRegex::try_new`] for the fallible version.
+    pub fn new(regex: String) -> Self {
+        Self::try_new(regex).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Constructs a new `ClearRegex`.
+    ///
+    /// Returns an error if the provided regex is malformed, uses an unsupported feature or contains
+    /// null characters "\0".
+    pub fn try_new(regex: String) -> Result<Self, FheStringError> {
+        check_no_null(&regex)?;
+
+        let ast = Parser::new(&regex).parse()?;
+
+        // The `Match` node is always there
+        if ast.num_nodes() + 1 > MAX_NODES {
+            return Err(FheStringError::InvalidRegex {
+                index: 0,
+                reason: "regex is too large",
+            });
+        }
+
+        let mut compiled = ClearRegex {
+            regex,
+            nodes: vec![RegexNode::Match],
+            classes: Vec::new(),
+            start: 0,
+        };
+        compiled.start = compiled.compile(&ast, 0);
+
+        Ok(compiled)
+    }
+
+    pub fn str(&self) -> &str {
+        &self.regex
+    }
+
+    pub(crate) fn nodes(&self) -> &[RegexNode] {
+        &self.nodes
+    }
+
+    pub(crate) fn classes(&self) -> &[Vec<(u8, u8)>] {
+        &self.classes
+    }
+
+    pub(crate) fn start(&self) -> usize {
+        self.start
+    }
+
+    // Returns the `Class` and `Match` nodes reachable from `node` without consuming any byte, and
+    // for each one whether reaching it requires the `$` anchor to hold. The `^` anchor only holds
+    // if `at_start` is true
+    pub(crate) fn closure(&self, node: usize, at_start: bool) -> Vec<(usize, bool)> {
+        let mut visited = vec![false; self.nodes.len()];
+        let mut result = Vec::new();
+
+        // First we visit the nodes that don't require `$`, and then the ones after a `$`
+        let mut after_end = Vec::new();
+
+        for needs_end in [false, true] {
+            let mut stack = if needs_end {
+                std::mem::take(&mut after_end)
+            } else {
+                vec![node]
+            };
+
+            while let Some(node) = stack.pop() {
+                if visited[node] {
+                    continue;
+                }
+                visited[node] = true;
+
+                match &self.nodes[node] {
+                    RegexNode::Match | RegexNode::Class { .. } => result.push((node, needs_end)),
+                    RegexNode::Split(next) => stack.extend(next),
+                    RegexNode::Start(next) => {
+                        if at_start {
+                            stack.push(*next);
+                        }
+                    }
+                    RegexNode::End(next) => {
+                        if needs_end {
+                            stack.push(*next);
+                        } else {
+                            after_end.push(*next);
+                        }
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    fn push(&mut self, node: RegexNode) -> usize {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    // Compiles the ast into nodes that continue with the `next` node, returning the first one
+    fn compile(&mut self, ast: &Ast, next: usize) -> usize {
+        match ast {
+            Ast::Empty => next,
+            Ast::Class(ranges) => {
+                let class = match self.classes.iter().position(|class| class == ranges) {
+                    Some(class) => class,
+                    None => {
+                        self.classes.push(ranges.clone());
+                        self.classes.len() - 1
+                    }
+                };
+
+                self.push(RegexNode::Class { class, next })
+            }
+            Ast::Start => self.push(RegexNode::Start(next)),
+            Ast::End => self.push(RegexNode::End(next)),
+            Ast::Concat(asts) => {
+                let mut next = next;
+                for ast in asts.iter().rev() {
+                    next = self.compile(ast, next);
+                }
+
+                next
+            }
+            Ast::Alt(asts) => {
+                let branches = asts.iter().map(|ast| self.compile(ast, next)).collect();
+
+                self.push(RegexNode::Split(branches))
+            }
+            Ast::Repeat { ast, min, max } => {
+                // First the optional repetitions, which go after the `min` mandatory ones
+                let mut next = match max {
+                    Some(max) => {
+                        let mut next = next;
+                        for _ in *min..*max {
+                            let body = self.compile(ast, next);
+                            next = self.push(RegexNode::Split(vec![body, next]));
+                        }
+
+                        next
+                    }
+                    None => {
+                        let split = self.push(RegexNode::Split(Vec::new()));
+                        let body = self.compile(ast, split);
+                        self.nodes[split] = RegexNode::Split(vec![body, next]);
+
+                        split
+                    }
+                };
+
+                for _ in 0..*min {
+                    next = self.compile(ast, next);
+                }
+
+                next
+            }
+        }
+    }
+}
+
+impl Ast {
+    // The number of levels of the ast, bounded by `MAX_DEPTH` once parsed
+    fn depth(&self) -> usize {
+        match self {
+            Ast::Empty | Ast::Class(_) | Ast::Start | Ast::End => 1,
+            Ast::Concat(asts) | Ast::Alt(asts) => {
+                1 + asts.iter().map(Ast::depth).max().unwrap_or(0)
+            }
+            Ast::Repeat { ast, .. } => 1 + ast.depth(),
+        }
+    }
+
+    // The number of nodes that `ClearRegex::compile` pushes for this ast, saturating on overflow
+    fn num_nodes(&self) -> u64 {
+        match self {
+            Ast::Empty => 0,
+            Ast::Class(_) | Ast::Start | Ast::End => 1,
+            Ast::Concat(asts) => asts
+                .iter()
+                .fold(0, |sum, ast| sum.saturating_add(ast.num_nodes())),
+            Ast::Alt(asts) => asts
+                .iter()
+                .fold(1, |sum, ast| sum.saturating_add(ast.num_nodes())),
+            Ast::Repeat { ast, min, max } => {
+                let body = ast.num_nodes();
+
+                // Each optional repetition has a split node, while `*` adds a single one
+                let (repetitions, splits) = match max {
+                    Some(max) => (*max as u64, (max - min) as u64),
+                    None => (*min as u64 + 1, 1),
+                };
+
+                body.saturating_mul(repetitions).saturating_add(splits)
+            }
+        }
+    }
+}
+
+fn byte_ast(byte: u8) -> Ast {
+    Ast::Class(vec![(byte, byte)])
+}
+
+// Matches any non ASCII char, as its UTF-8 bytes. The encrypted strings are valid UTF-8, so we
+// only need to check the ranges of the leading and continuation bytes
+fn non_ascii_char_ast() -> Ast {
+    let cont = || Ast::Class(vec![(0x80, 0xBF)]);
+
+    Ast::Alt(vec![
+        Ast::Concat(vec![Ast::Class(vec![(0xC0, 0xDF)]), cont()]),
+        Ast::Concat(vec![Ast::Class(vec![(0xE0, 0xEF)]), cont(), cont()]),
+        Ast::Concat(vec![Ast::Class(vec![(0xF0, 0xF7)]), cont(), cont(), cont()]),
+    ])
+}
+
+// Sorts and merges the ranges
+fn normalize(mut ranges: Vec<(u8, u8)>) -> Vec<(u8, u8)> {
+    ranges.sort_unstable();
+
+    let mut result: Vec<(u8, u8)> = Vec::with_capacity(ranges.len());
+    for (lo, hi) in ranges {
+        match result.last_mut() {
+            Some(last) if lo as u16 <= last.1 as u16 + 1 => last.1 = last.1.max(hi),
+            _ => result.push((lo, hi)),
+        }
+    }
+
+    result
+}
+
+// Returns an ast matching any char that is not in the ASCII ranges (nor null)
+fn negated_class_ast(ranges: Vec<(u8, u8)>) -> Ast {
+    let mut complement = Vec::new();
+    let mut lo = 1;
+
+    for (range_lo, range_hi) in normalize(ranges) {
+        if range_lo > lo {
+            complement.push((lo, range_lo - 1));
+        }
+        lo = lo.max(range_hi + 1);
+    }
+
+    if lo <= 0x7F {
+        complement.push((lo, 0x7F));
+    }
+
+    Ast::Alt(vec![Ast::Class(complement), non_ascii_char_ast()])
+}
+
+fn perl_class(c: char) -> Option<(Vec<(u8, u8)>, bool)> {
+    let ranges = match c.to_ascii_lowercase() {
+        'd' => vec![(b'0', b'9')],
+        'w' => vec![(b'0', b'9'), (b'A', b'Z'), (b'_', b'_'), (b'a', b'z')],
+        's' => vec![(b'\t', b'\r'), (b' ', b' ')],
+        _ => return None,
+    };
+
+    Some((ranges, c.is_ascii_uppercase()))
+}
+
+struct Parser {
+    chars: Vec<char>,
+    index: usize,
+    // The number of groups that are currently open
+    depth: usize,
+}
+
+impl Parser {
+    fn new(regex: &str) -> Self {
+        Parser {
+            chars: regex.chars().collect(),
+            index: 0,
+            depth: 0,
+        }
+    }
+
+    fn error(&self, reason: &'static str) -> FheStringError {
+        FheStringError::InvalidRegex {
+            index: self.index,
+            reason,
+        }
+    }
+
+    fn escaped_byte(&self, c: char) -> Result<u8, FheStringError> {
+        match c {
+            'n' => Ok(b'\n'),
+            't' => Ok(b'\t'),
+            'r' => Ok(b'\r'),
+            'f' => Ok(0x0C),
+            'v' => Ok(0x0B),
+            c if c.is_ascii_punctuation() => Ok(c as u8),
+            _ => Err(self.error("unsupported escape")),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        if c.is_some() {
+            self.index += 1;
+        }
+
+        c
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let is_next = self.peek() == Some(c);
+        if is_next {
+            self.index += 1;
+        }
+
+        is_next
+    }
+
+    fn parse(mut self) -> Result<Ast, FheStringError> {
+        let ast = self.parse_alt()?;
+
+        match self.peek() {
+            None => Ok(ast),
+            Some(_) => Err(self.error("unopened group")),
+        }
+    }
+
+    fn parse_alt(&mut self) -> Result<Ast, FheStringError> {
+        let mut branches = vec![self.parse_concat()?];
+
+        while self.eat('|') {
+            branches.push(self.parse_concat()?);
+        }
+
+        if branches.len() == 1 {
+            Ok(branches.pop().unwrap())
+        } else {
+            Ok(Ast::Alt(branches))
+        }
+    }
+
+    fn parse_concat(&mut self) -> Result<Ast, FheStringError> {
+        let mut asts = Vec::new();
+
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+
+            let mut ast = self.parse_atom()?;
+            let mut depth = ast.depth();
+
+            loop {
+                if matches!(self.peek(), Some('?' | '*' | '+' | '{')) {
+                    depth += 1;
+
+                    // Each open group adds an `Alt` and a `Concat` level above this ast
+                    if 2 * self.depth + depth > MAX_DEPTH {
+                        return Err(self.error("regex is too deeply nested"));
+                    }
+                }
+
+                let (min, max) = match self.peek() {
+                    Some('?') => (0, Some(1)),
+                    Some('*') => (0, None),
+                    Some('+') => (1, None),
+                    Some('{') => {
+                        self.index += 1;
+                        self.parse_counted()?
+                    }
+                    _ => break,
+                };
+
+                // Consume the operator, or the `}` of a counted repetition
+                self.index += 1;
+
+                ast = Ast::Repeat {
+                    ast: Box::new(ast),
+                    min,
+                    max,
+                };
+            }
+
+            asts.push(ast);
+        }
+
+        Ok(Ast::Concat(asts))
+    }
+
+    // Parses `n}`, `n,}` or `n,m}` after a `{`, leaving the final `}` to be consumed
+    fn parse_counted(&mut self) -> Result<(u32, Option<u32>), FheStringError> {
+        let min = self.parse_number()?;
+
+        let max = if self.eat(',') {
+            if self.peek() == Some('}') {
+                None
+            } else {
+                Some(self.parse_number()?)
+            }
+        } else {
+            Some(min)
+        };
+
+        if self.peek() != Some('}') {
+            return Err(self.error("unclosed counted repetition"));
+        }
+
+        if max.is_some_and(|max| max < min) {
+            return Err(self.error("invalid counted repetition range"));
+        }
+
+        Ok((min, max))
+    }
+
+    fn parse_number(&mut self) -> Result<u32, FheStringError> {
+        let start = self.index;
+
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.index += 1;
+        }
+
+        let number: String = self.chars[start..self.index].iter().collect();
+
+        match number.parse() {
+            Ok(number) if number <= MAX_REPETITIONS => Ok(number),
+            Ok(_) => Err(self.error("counted repetition is too large")),
+            Err(_) => Err(self.error("expected a number in counted repetition")),
+        }
+    }
+
+    fn parse_atom(&mut self) -> Result<Ast, FheStringError> {
+        match self.next() {
+            Some('(') => {
+                // Each group adds an `Alt` and a `Concat` level to the ast
+                if 2 * (self.depth + 1) > MAX_DEPTH {
+                    self.index -= 1;
+                    return Err(self.error("regex is too deeply nested"));
+                }
+
+                if self.eat('?') && !self.eat(':') {
+                    return Err(self.error("unsupported group flags"));
+                }
+
+                self.depth += 1;
+                let ast = self.parse_alt()?;
+                self.depth -= 1;
+
+                if !self.eat(')') {
+                    return Err(self.error("unclosed group"));
+                }
+
+                Ok(ast)
+            }
+            Some('[') => self.parse_class(),
+            // Like in the `regex` crate, `.` doesn't match `\n`
+            Some('.') => Ok(Ast::Alt(vec![
+                Ast::Class(vec![(0x01, 0x09), (0x0B, 0x7F)]),
+                non_ascii_char_ast(),
+            ])),
+            Some('^') => Ok(Ast::Start),
+            Some('$') => Ok(Ast::End),
+            Some('\\') => {
+                let c = self.next().ok_or_else(|| self.error("incomplete escape"))?;
+
+                if let Some((ranges, negated)) = perl_class(c) {
+                    return Ok(if negated {
+                        negated_class_ast(ranges)
+                    } else {
+                        Ast::Class(ranges)
+                    });
+                }
+
+                self.escaped_byte(c).map(byte_ast)
+            }
+            Some('?' | '*' | '+' | '{') => {
+                self.index -= 1;
+                Err(self.error("repetition operator without an expression"))
+            }
+            Some(c) => {
+                let mut bytes = [0; 4];
+                let bytes = c.encode_utf8(&mut bytes).bytes().map(byte_ast).collect();
+
+                Ok(Ast::Concat(bytes))
+            }
+            None => Ok(Ast::Empty),
+        }
+    }
+
+    // Parses a class after the opening `[`
+    fn parse_class(&mut self) -> Result<Ast, FheStringError> {
+        let negated = self.eat('^');
+        let first = self.index;
+        let mut ranges = Vec::new();
+
+        loop {
+            let lo = match self.next() {
+                // A `]` is a literal if it's the first char of the class
+                Some(']') if self.index - 1 != first => break,
+                Some('\\') => {
+                    let c = self.next().ok_or_else(|| self.error("incomplete escape"))?;
+
+                    match perl_class(c) {
+                        Some((class_ranges, false)) => {
+                            ranges.extend(class_ranges);
+                            continue;
+                        }
+                        Some((_, true)) => return Err(self.error("unsupported negated class")),
+                        None => self.escaped_byte(c)?,
+                    }
+                }
+                Some(c) if c.is_ascii() => c as u8,
+                Some(_) => return Err(self.error("non ASCII char in class")),
+                None => return Err(self.error("unclosed class")),
+            };
+
+            // A `-` is a literal if it's the last char of the class
+            let hi = if self.peek() == Some('-') && self.chars.get(self.index + 1) != Some(&']') {
+                self.index += 1;
+
+                match self.next() {
+                    Some('\\') => {
+                        let c = self.next().ok_or_else(|| self.error("incomplete escape"))?;
+                        self.escaped_byte(c)?
+                    }
+                    Some(c) if c.is_ascii() => c as u8,
+                    Some(_) => return Err(self.error("non ASCII char in class")),
+                    None => return Err(self.error("unclosed class")),
+                }
+            } else {
+                lo
+            };
+
+            if hi < lo {
+                return Err(self.error("invalid class range"));
+            }
+
+            ranges.push((lo, hi));
+        }
+
+        if negated {
+            Ok(negated_class_ast(ranges))
+        } else {
+            Ok(Ast::Class(normalize(ranges)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regex_errors() {
+        let test_cases = [
+            ("(ab", 3, "unclosed group"),
+            ("ab)", 2, "unopened group"),
+            ("*a", 0, "repetition operator without an expression"),
+            ("[a-", 3, "unclosed class"),
+            ("[]", 2, "unclosed class"),
+            ("[^]", 3, "unclosed class"),
+            ("[z-a]", 4, "invalid class range"),
+            ("[é]", 2, "non ASCII char in class"),
+            ("a{2,1}", 5, "invalid counted repetition range"),
+            ("a{2", 3, "unclosed counted repetition"),
+            ("\\q", 2, "unsupported escape"),
+        ];
+
+        for (regex, index, reason) in test_cases {
+            let err = ClearRegex::try_new(regex.to_string()).err();
+
+            assert_eq!(err, Some(FheStringError::InvalidRegex { index, reason }));
+        }
+
+        // Each repetition is compiled again, so nested ones quickly exceed the node limit
+        assert_eq!(
+            ClearRegex::try_new("((a{255}){255}){255}".to_string()).err(),
+            Some(FheStringError::InvalidRegex {
+                index: 0,
+                reason: "regex is too large"
+            })
+        );
+        assert!(ClearRegex::try_new("(a{50}){50}".to_string()).is_ok());
+
+        // Nested groups and repetitions are rejected before they overflow the stack
+        let test_cases = [
+            ("(".repeat(100_000), 64),
+            (format!("a{}", "?".repeat(100_000)), 127),
+            (format!("{}a{}", "(".repeat(60), "?".repeat(100_000)), 67),
+        ];
+
+        for (regex, index) in test_cases {
+            assert_eq!(
+                ClearRegex::try_new(regex).err(),
+                Some(FheStringError::InvalidRegex {
+                    index,
+                    reason: "regex is too deeply nested"
+                })
+            );
+        }
+        assert!(ClearRegex::try_new(format!("{}a{}", "(".repeat(40), ")*".repeat(40))).is_ok());
+
+        assert_eq!(
+            ClearRegex::try_new("a\0".to_string()).err(),
+            Some(FheStringError::ContainsNull)
+        );
+    }
+}
@@ -1,7 +1,17 @@
 use crate::ciphertext::{FheString, GenericPattern};
-use crate::server_key::{FheStringIsEmpty, ServerKey};
+use crate::server_key::{FheOrdering, FheStringIsEmpty, ServerKey};
+use crate::N;
+use rayon::prelude::*;
 use tfhe::integer::BooleanBlock;
 
+#[derive(Clone, Copy)]
+enum OrderingOp {
+    Lt,
+    Gt,
+    Le,
+    Ge,
+}
+
 impl ServerKey {
     fn eq_length_checks(&self, lhs: &FheString, rhs: &FheString) -> Option<BooleanBlock> {
         let lhs_len = lhs.chars().len();
@@ -52,7 +62,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (s1, s2) = ("hello", "hello");
     ///
@@ -79,7 +90,8 @@ impl ServerKey {
         let mut lhs_uint = lhs.to_uint(self);
         match rhs {
             GenericPattern::Clear(rhs) => {
-                let rhs_clear_uint = self.pad_cipher_and_cleartext_lsb(&mut lhs_uint, rhs.str());
+                let rhs_clear_uint =
+                    self.pad_cipher_and_cleartext_lsb(&mut lhs_uint, rhs.str().as_bytes());
 
                 self.key.scalar_eq_parallelized(&lhs_uint, rhs_clear_uint)
             }
@@ -103,7 +115,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (s1, s2) = ("hello", "world");
     ///
@@ -121,113 +134,227 @@ impl ServerKey {
         self.key.boolean_bitnot(&eq)
     }
 
-    /// Returns `true` if the first encrypted string is less than the second encrypted string.
+    // Lexicographically compares the strings by comparing their UInt representations, where the
+    // first char is the most significant and nulls are the lowest chars
+    fn ordering_comp(&self, lhs: &FheString, rhs: &GenericPattern, op: OrderingOp) -> BooleanBlock {
+        let [result] = self.ordering_comps(lhs, rhs, [op]);
+
+        result
+    }
+
+    // Computes each of the comparisons, in parallel, on the same UInt representations, so that we
+    // only build and pad them once
+    fn ordering_comps<const K: usize>(
+        &self,
+        lhs: &FheString,
+        rhs: &GenericPattern,
+        ops: [OrderingOp; K],
+    ) -> [BooleanBlock; K] {
+        let results: Vec<_> = match rhs {
+            // The clear UInt only has room for the chars of a `ClearString`
+            GenericPattern::Clear(rhs) if lhs.chars().len() <= N * 8 => {
+                let mut lhs_uint = lhs.to_uint(self);
+                let rhs_clear_uint =
+                    self.pad_cipher_and_cleartext_lsb(&mut lhs_uint, rhs.str().as_bytes());
+
+                ops.par_iter()
+                    .map(|op| match op {
+                        OrderingOp::Lt => {
+                            self.key.scalar_lt_parallelized(&lhs_uint, rhs_clear_uint)
+                        }
+                        OrderingOp::Gt => {
+                            self.key.scalar_gt_parallelized(&lhs_uint, rhs_clear_uint)
+                        }
+                        OrderingOp::Le => {
+                            self.key.scalar_le_parallelized(&lhs_uint, rhs_clear_uint)
+                        }
+                        OrderingOp::Ge => {
+                            self.key.scalar_ge_parallelized(&lhs_uint, rhs_clear_uint)
+                        }
+                    })
+                    .collect()
+            }
+            GenericPattern::Clear(rhs) => {
+                let rhs = FheString::trivial(self, rhs.str());
+
+                return self.enc_ordering_comps(lhs, &rhs, ops);
+            }
+            GenericPattern::Enc(rhs) => return self.enc_ordering_comps(lhs, rhs, ops),
+        };
+
+        results
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("There's one result per op"))
+    }
+
+    fn enc_ordering_comps<const K: usize>(
+        &self,
+        lhs: &FheString,
+        rhs: &FheString,
+        ops: [OrderingOp; K],
+    ) -> [BooleanBlock; K] {
+        let (mut lhs_uint, mut rhs_uint) = (lhs.to_uint(self), rhs.to_uint(self));
+
+        self.pad_ciphertexts_lsb(&mut lhs_uint, &mut rhs_uint);
+
+        let results: Vec<_> = ops
+            .par_iter()
+            .map(|op| match op {
+                OrderingOp::Lt => self.key.lt_parallelized(&lhs_uint, &rhs_uint),
+                OrderingOp::Gt => self.key.gt_parallelized(&lhs_uint, &rhs_uint),
+                OrderingOp::Le => self.key.le_parallelized(&lhs_uint, &rhs_uint),
+                OrderingOp::Ge => self.key.ge_parallelized(&lhs_uint, &rhs_uint),
+            })
+            .collect();
+
+        results
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("There's one result per op"))
+    }
+
+    // `lt` for two encrypted strings, which doesn't need an owned `GenericPattern` for `rhs`
+    pub(super) fn enc_lt(&self, lhs: &FheString, rhs: &FheString) -> BooleanBlock {
+        let [result] = self.enc_ordering_comps(lhs, rhs, [OrderingOp::Lt]);
+
+        result
+    }
+
+    /// Returns `true` if the encrypted string is less than the pattern (either encrypted or
+    /// clear).
     ///
     /// Returns `false` otherwise.
     ///
+    /// The pattern for comparison (`rhs`) can be specified as either `GenericPattern::Clear` for a
+    /// clear string or `GenericPattern::Enc` for an encrypted string.
+    ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (s1, s2) = ("apple", "banana");
     ///
     /// let enc_s1 = FheString::new(&ck, &s1, None);
-    /// let enc_s2 = FheString::new(&ck, &s2, None);
+    /// let enc_s2 = GenericPattern::Enc(FheString::new(&ck, &s2, None));
     ///
     /// let result = sk.lt(&enc_s1, &enc_s2);
     /// let is_lt = ck.key().decrypt_bool(&result);
     ///
     /// assert!(is_lt); // "apple" is less than "banana"
     /// ```
-    pub fn lt(&self, lhs: &FheString, rhs: &FheString) -> BooleanBlock {
-        let mut lhs_uint = lhs.to_uint(self);
-        let mut rhs_uint = rhs.to_uint(self);
-
-        self.pad_ciphertexts_lsb(&mut lhs_uint, &mut rhs_uint);
-
-        self.key.lt_parallelized(&lhs_uint, &rhs_uint)
+    pub fn lt(&self, lhs: &FheString, rhs: &GenericPattern) -> BooleanBlock {
+        self.ordering_comp(lhs, rhs, OrderingOp::Lt)
     }
 
-    /// Returns `true` if the first encrypted string is greater than the second encrypted string.
+    /// Returns `true` if the encrypted string is greater than the pattern (either encrypted or
+    /// clear).
     ///
     /// Returns `false` otherwise.
     ///
+    /// The pattern for comparison (`rhs`) can be specified as either `GenericPattern::Clear` for a
+    /// clear string or `GenericPattern::Enc` for an encrypted string.
+    ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (s1, s2) = ("banana", "apple");
     ///
     /// let enc_s1 = FheString::new(&ck, &s1, None);
-    /// let enc_s2 = FheString::new(&ck, &s2, None);
+    /// let enc_s2 = GenericPattern::Enc(FheString::new(&ck, &s2, None));
     ///
     /// let result = sk.gt(&enc_s1, &enc_s2);
     /// let is_gt = ck.key().decrypt_bool(&result);
     ///
     /// assert!(is_gt); // "banana" is greater than "apple"
     /// ```
-    pub fn gt(&self, lhs: &FheString, rhs: &FheString) -> BooleanBlock {
-        let mut lhs_uint = lhs.to_uint(self);
-        let mut rhs_uint = rhs.to_uint(self);
-
-        self.pad_ciphertexts_lsb(&mut lhs_uint, &mut rhs_uint);
-
-        self.key.gt_parallelized(&lhs_uint, &rhs_uint)
+    pub fn gt(&self, lhs: &FheString, rhs: &GenericPattern) -> BooleanBlock {
+        self.ordering_comp(lhs, rhs, OrderingOp::Gt)
     }
 
-    /// Returns `true` if the first encrypted string is less than or equal to the second encrypted
-    /// string.
+    /// Returns `true` if the encrypted string is less than or equal to the pattern (either
+    /// encrypted or clear).
     ///
     /// Returns `false` otherwise.
     ///
+    /// The pattern for comparison (`rhs`) can be specified as either `GenericPattern::Clear` for a
+    /// clear string or `GenericPattern::Enc` for an encrypted string.
+    ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (s1, s2) = ("apple", "banana");
     ///
     /// let enc_s1 = FheString::new(&ck, &s1, None);
-    /// let enc_s2 = FheString::new(&ck, &s2, None);
+    /// let enc_s2 = GenericPattern::Enc(FheString::new(&ck, &s2, None));
     ///
     /// let result = sk.le(&enc_s1, &enc_s2);
     /// let is_le = ck.key().decrypt_bool(&result);
     ///
     /// assert!(is_le); // "apple" is less than or equal to "banana"
     /// ```
-    pub fn le(&self, lhs: &FheString, rhs: &FheString) -> BooleanBlock {
-        let mut lhs_uint = lhs.to_uint(self);
-        let mut rhs_uint = rhs.to_uint(self);
-
-        self.pad_ciphertexts_lsb(&mut lhs_uint, &mut rhs_uint);
-
-        self.key.le_parallelized(&lhs_uint, &rhs_uint)
+    pub fn le(&self, lhs: &FheString, rhs: &GenericPattern) -> BooleanBlock {
+        self.ordering_comp(lhs, rhs, OrderingOp::Le)
     }
 
-    /// Returns `true` if the first encrypted string is greater than or equal to the second
-    /// encrypted string.
+    /// Returns `true` if the encrypted string is greater than or equal to the pattern (either
+    /// encrypted or clear).
     ///
     /// Returns `false` otherwise.
     ///
+    /// The pattern for comparison (`rhs`) can be specified as either `GenericPattern::Clear` for a
+    /// clear string or `GenericPattern::Enc` for an encrypted string. With a clear bound, range
+    /// filters like `name >= "M"` are computed with scalar comparisons.
+    ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
-    /// let (s1, s2) = ("banana", "apple");
+    /// let (s1, s2) = ("Martin", "M");
     ///
-    /// let enc_s1 = FheString::new(&ck, &s1, None);
-    /// let enc_s2 = FheString::new(&ck, &s2, None);
+    /// let enc_s1 = FheString::new(&ck, &s1, Some(2));
+    /// let clear_s2 = GenericPattern::Clear(ClearString::new(s2.to_string()));
     ///
-    /// let result = sk.ge(&enc_s1, &enc_s2);
+    /// let result = sk.ge(&enc_s1, &clear_s2);
     /// let is_ge = ck.key().decrypt_bool(&result);
     ///
-    /// assert!(is_ge); // "banana" is greater than or equal to "apple"
+    /// assert!(is_ge); // "Martin" is greater than or equal to "M"
     /// ```
-    pub fn ge(&self, lhs: &FheString, rhs: &FheString) -> BooleanBlock {
-        let mut lhs_uint = lhs.to_uint(self);
-        let mut rhs_uint = rhs.to_uint(self);
+    pub fn ge(&self, lhs: &FheString, rhs: &GenericPattern) -> BooleanBlock {
+        self.ordering_comp(lhs, rhs, OrderingOp::Ge)
+    }
 
-        self.pad_ciphertexts_lsb(&mut lhs_uint, &mut rhs_uint);
+    /// Returns the lexicographic [`Ordering`](std::cmp::Ordering) of an encrypted string and a
+    /// pattern (either encrypted or clear) as an encrypted [`FheOrdering`], which can be
+    /// decrypted with [`ClientKey::decrypt_ordering`](crate::ClientKey::decrypt_ordering).
+    ///
+    /// The pattern for comparison (`rhs`) can be specified as either `GenericPattern::Clear` for a
+    /// clear string or `GenericPattern::Enc` for an encrypted string.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// use std::cmp::Ordering;
+    ///
+    /// let (ck, sk) = gen_keys();
+    /// let (s1, s2) = ("apple", "apples");
+    ///
+    /// let enc_s1 = FheString::new(&ck, &s1, Some(1));
+    /// let enc_s2 = GenericPattern::Enc(FheString::new(&ck, &s2, None));
+    ///
+    /// let result = sk.cmp(&enc_s1, &enc_s2);
+    ///
+    /// assert_eq!(ck.decrypt_ordering(&result), s1.cmp(s2));
+    /// assert_eq!(ck.decrypt_ordering(&result), Ordering::Less);
+    /// ```
+    pub fn cmp(&self, lhs: &FheString, rhs: &GenericPattern) -> FheOrdering {
+        let [is_lt, is_gt] = self.ordering_comps(lhs, rhs, [OrderingOp::Lt, OrderingOp::Gt]);
 
-        self.key.ge_parallelized(&lhs_uint, &rhs_uint)
+        FheOrdering { is_lt, is_gt }
     }
 }
@@ -0,0 +1,348 @@
+use crate::ciphertext::{FheAsciiChar, FheString, GenericPattern};
+use crate::parameters::num_blocks;
+use crate::server_key::{MaybeClear, ServerKey};
+use rayon::prelude::*;
+use tfhe::integer::{BooleanBlock, IntegerCiphertext, RadixCiphertext};
+
+// The rhs bytes, which we compare with the lhs chars and we insert when editing lhs
+enum Rhs<'a> {
+    Clear(&'a [u8]),
+    Enc(&'a [FheAsciiChar], bool),
+}
+
+impl Rhs<'_> {
+    fn new(rhs: &GenericPattern) -> Rhs<'_> {
+        match rhs {
+            GenericPattern::Clear(rhs) => Rhs::Clear(rhs.str().as_bytes()),
+            GenericPattern::Enc(rhs) => Rhs::Enc(rhs.chars(), rhs.is_padded()),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Rhs::Clear(bytes) => bytes.len(),
+            Rhs::Enc(chars, _) => chars.len(),
+        }
+    }
+
+    fn truncate(self, max_len: usize) -> Self {
+        match self {
+            Rhs::Clear(bytes) => Rhs::Clear(&bytes[..bytes.len().min(max_len)]),
+            Rhs::Enc(chars, padded) => Rhs::Enc(&chars[..chars.len().min(max_len)], padded),
+        }
+    }
+}
+
+impl ServerKey {
+    // Padding nulls are free to insert, delete or substitute by another null. This way the distance
+    // between two padded strings is the distance between the actual strings
+    fn non_null(&self, char: &FheAsciiChar, padded: bool) -> MaybeClear {
+        if padded {
+            MaybeClear::Cipher(self.key.scalar_ne_parallelized(char.ciphertext(), 0u8))
+        } else {
+            MaybeClear::Clear(true)
+        }
+    }
+
+    fn rhs_non_null(&self, rhs: &Rhs, j: usize) -> MaybeClear {
+        match rhs {
+            Rhs::Clear(_) => MaybeClear::Clear(true),
+            Rhs::Enc(chars, padded) => self.non_null(&chars[j], *padded),
+        }
+    }
+
+    fn chars_ne(&self, lhs: &FheAsciiChar, rhs: &Rhs, j: usize) -> BooleanBlock {
+        match rhs {
+            Rhs::Clear(bytes) => self.key.scalar_ne_parallelized(lhs.ciphertext(), bytes[j]),
+            Rhs::Enc(chars, _) => self
+                .key
+                .ne_parallelized(lhs.ciphertext(), chars[j].ciphertext()),
+        }
+    }
+
+    fn add_cost(&self, distance: &RadixCiphertext, cost: &MaybeClear) -> RadixCiphertext {
+        let num_blocks = distance.blocks().len();
+
+        match cost {
+            MaybeClear::Clear(false) => distance.clone(),
+            MaybeClear::Clear(true) => self.key.scalar_add_parallelized(distance, 1u8),
+            MaybeClear::Cipher(cost) => {
+                let cost = cost.clone().into_radix(num_blocks, &self.key);
+                self.key.add_parallelized(distance, &cost)
+            }
+        }
+    }
+
+    // Computes the edit distance matrix along its anti-diagonals, as all the cells of one only
+    // depend on the previous two. With a `band`, the cells that are further than `band` from the
+    // main diagonal (except for the last row and column) aren't computed, and the distances are
+    // saturated at `band + 1`, i.e. the result is only exact if it's not greater than `band`
+    fn edit_distance(
+        &self,
+        lhs: &[FheAsciiChar],
+        lhs_padded: bool,
+        rhs: &Rhs,
+        band: Option<usize>,
+        num_blocks: usize,
+    ) -> Option<RadixCiphertext> {
+        let (n, m) = (lhs.len(), rhs.len());
+
+        // The cost of deleting each lhs char and of inserting each rhs char
+        let (del_costs, ins_costs): (Vec<_>, Vec<_>) = rayon::join(
+            || {
+                lhs.par_iter()
+                    .map(|char| self.non_null(char, lhs_padded))
+                    .collect()
+            },
+            || {
+                (0..m)
+                    .into_par_iter()
+                    .map(|j| self.rhs_non_null(rhs, j))
+                    .collect()
+            },
+        );
+
+        let in_band = |i: usize, j: usize| match band {
+            Some(band) => i.abs_diff(j) <= band || i == n || j == m,
+            None => true,
+        };
+
+        // `None` means that the cell isn't computed, i.e. that its distance is greater than `band`
+        let mut dist: Vec<Vec<Option<RadixCiphertext>>> = vec![vec![None; m + 1]; n + 1];
+        dist[0][0] = Some(self.key.create_trivial_zero_radix(num_blocks));
+
+        for diagonal in 1..n + m + 1 {
+            let cells: Vec<_> = (diagonal.saturating_sub(m)..n.min(diagonal) + 1)
+                .into_par_iter()
+                .map(|i| (i, diagonal - i))
+                .filter(|&(i, j)| in_band(i, j))
+                .map(|(i, j)| {
+                    let deletion = i
+                        .checked_sub(1)
+                        .and_then(|prev_i| dist[prev_i][j].as_ref())
+                        .map(|prev| (prev, del_costs[i - 1].clone()));
+
+                    let insertion = j
+                        .checked_sub(1)
+                        .and_then(|prev_j| dist[i][prev_j].as_ref())
+                        .map(|prev| (prev, ins_costs[j - 1].clone()));
+
+                    // The substitution cost is whether the chars are different
+                    let substitution = (i > 0 && j > 0)
+                        .then(|| dist[i - 1][j - 1].as_ref())
+                        .flatten()
+                        .map(|prev| {
+                            let ne = self.chars_ne(&lhs[i - 1], rhs, j - 1);
+                            (prev, MaybeClear::Cipher(ne))
+                        });
+
+                    let candidates: Vec<_> = [deletion, insertion, substitution]
+                        .into_par_iter()
+                        .flatten()
+                        .map(|(prev, cost)| self.add_cost(prev, &cost))
+                        .collect();
+
+                    let min = candidates
+                        .into_iter()
+                        .reduce(|a, b| self.key.min_parallelized(&a, &b))
+                        .map(|min| match band {
+                            Some(band) => self.key.scalar_min_parallelized(&min, band as u64 + 1),
+                            None => min,
+                        });
+
+                    (i, j, min)
+                })
+                .collect();
+
+            for (i, j, cell) in cells {
+                dist[i][j] = cell;
+            }
+        }
+
+        dist[n][m].take()
+    }
+
+    /// Returns the Levenshtein distance between an encrypted string and a pattern (either
+    /// encrypted or clear), i.e. the minimum number of single char insertions, deletions or
+    /// substitutions needed to change one into the other.
+    ///
+    /// The distance is computed over the UTF-8 bytes, which are the chars for ASCII strings, and
+    /// padding nulls are ignored. Only the first `max_len` chars of each string are taken into
+    /// account, which bounds the cost of strings with a lot of padding (that is proportional to
+    /// the product of both lengths). The distance is returned as an encrypted `RadixCiphertext`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    ///
+    /// let enc_s1 = FheString::new(&ck, "kitten", Some(2));
+    /// let enc_s2 = GenericPattern::Clear(ClearString::new("sitting".to_string()));
+    ///
+    /// let distance = sk.levenshtein(&enc_s1, &enc_s2, 8);
+    /// let clear_distance: u32 = ck.key().decrypt_radix(&distance);
+    ///
+    /// assert_eq!(clear_distance, 3);
+    /// ```
+    pub fn levenshtein(
+        &self,
+        lhs: &FheString,
+        rhs: &GenericPattern,
+        max_len: usize,
+    ) -> RadixCiphertext {
+        let lhs_chars = &lhs.chars()[..lhs.chars().len().min(max_len)];
+        let rhs = Rhs::new(rhs).truncate(max_len);
+
+        self.edit_distance(lhs_chars, lhs.is_padded(), &rhs, None, self.index_blocks())
+            .expect("Without a band every cell is computed")
+    }
+
+    /// Returns the Hamming distance between an encrypted string and a pattern (either encrypted
+    /// or clear), i.e. the number of positions at which their chars are different.
+    ///
+    /// The distance is computed over the UTF-8 bytes and padding nulls are ignored. If the lengths
+    /// are different, the excess chars of the longer string are counted as differences. The
+    /// distance is returned as an encrypted `RadixCiphertext`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    ///
+    /// let enc_s1 = FheString::new(&ck, "karolin", None);
+    /// let enc_s2 = GenericPattern::Enc(FheString::new(&ck, "kathrin", Some(3)));
+    ///
+    /// let distance = sk.hamming(&enc_s1, &enc_s2);
+    /// let clear_distance: u32 = ck.key().decrypt_radix(&distance);
+    ///
+    /// assert_eq!(clear_distance, 3);
+    /// ```
+    pub fn hamming(&self, lhs: &FheString, rhs: &GenericPattern) -> RadixCiphertext {
+        let lhs_chars = lhs.chars();
+        let rhs = Rhs::new(rhs);
+        let (n, m) = (lhs_chars.len(), rhs.len());
+
+        let differences: Vec<_> = (0..n.max(m))
+            .into_par_iter()
+            .map(|i| match (lhs_chars.get(i), i < m) {
+                (Some(char), true) => MaybeClear::Cipher(self.chars_ne(char, &rhs, i)),
+                (Some(char), false) => self.non_null(char, lhs.is_padded()),
+                (None, _) => self.rhs_non_null(&rhs, i),
+            })
+            .collect();
+
+        let clear_count = differences
+            .iter()
+            .filter(|diff| matches!(diff, MaybeClear::Clear(true)))
+            .count();
+
+        let enc_differences: Vec<_> = differences
+            .into_par_iter()
+            .filter_map(|diff| match diff {
+                MaybeClear::Cipher(diff) => Some(diff.into_radix(self.index_blocks(), &self.key)),
+                MaybeClear::Clear(_) => None,
+            })
+            .collect();
+
+        let distance = self
+            .key
+            .sum_ciphertexts_parallelized(enc_differences.iter())
+            .unwrap_or_else(|| self.key.create_trivial_zero_radix(self.index_blocks()));
+
+        self.key
+            .scalar_add_parallelized(&distance, clear_count as u32)
+    }
+
+    /// Returns `true` if the Levenshtein distance between an encrypted string and a pattern
+    /// (either encrypted or clear) is at most `k`. See [`ServerKey::levenshtein`].
+    ///
+    /// This is cheaper than computing the full distance, as only the edits that keep the strings
+    /// within `k` positions of each other are considered, and the intermediate distances are
+    /// bounded by `k + 1` (so they are encrypted with fewer blocks).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    ///
+    /// let enc_s1 = FheString::new(&ck, "color", Some(4));
+    /// let enc_s2 = GenericPattern::Enc(FheString::new(&ck, "colour", None));
+    ///
+    /// let result = sk.within_distance(&enc_s1, &enc_s2, 1);
+    /// assert!(ck.key().decrypt_bool(&result));
+    ///
+    /// let result = sk.within_distance(&enc_s1, &enc_s2, 0);
+    /// assert!(!ck.key().decrypt_bool(&result));
+    /// ```
+    pub fn within_distance(&self, lhs: &FheString, rhs: &GenericPattern, k: usize) -> BooleanBlock {
+        let rhs = Rhs::new(rhs);
+
+        // Enough blocks to hold `k + 1` plus one edit
+        let bits = (k as u64 + 2).ilog2() as usize + 1;
+        let num_blocks = num_blocks(bits, self.key.message_modulus());
+
+        match self.edit_distance(lhs.chars(), lhs.is_padded(), &rhs, Some(k), num_blocks) {
+            Some(distance) => self.key.scalar_le_parallelized(&distance, k as u64),
+            None => self.key.create_trivial_boolean_block(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ciphertext::{ClearString, FheString, GenericPattern};
+    use crate::server_key::gen_keys;
+
+    #[test]
+    fn test_edit_distance() {
+        let (ck, sk) = gen_keys();
+
+        // (lhs, padding, rhs, padding, levenshtein, hamming)
+        let test_cases = [
+            ("", 0, "", 0, 0, 0),
+            ("", 2, "abc", 0, 3, 3),
+            ("abc", 1, "", 1, 3, 3),
+            ("abc", 0, "abc", 2, 0, 0),
+            ("kitten", 1, "sitting", 0, 3, 3),
+            ("flaw", 0, "lawn", 1, 2, 4),
+            ("abcd", 2, "abdc", 0, 2, 2),
+            ("añb", 0, "anb", 0, 2, 3),
+        ];
+
+        for (lhs, lhs_pad, rhs, rhs_pad, expected_lev, expected_hamming) in test_cases {
+            let enc_lhs = FheString::new_utf8(&ck, lhs, Some(lhs_pad));
+
+            let clear_rhs = GenericPattern::Clear(ClearString::new(rhs.to_string()));
+            let enc_rhs = GenericPattern::Enc(FheString::new_utf8(&ck, rhs, Some(rhs_pad)));
+
+            for rhs_pat in [clear_rhs, enc_rhs] {
+                let distance = sk.levenshtein(&enc_lhs, &rhs_pat, 16);
+                let distance: u32 = ck.key().decrypt_radix(&distance);
+                assert_eq!(distance, expected_lev, "levenshtein {lhs} {rhs}");
+
+                let distance = sk.hamming(&enc_lhs, &rhs_pat);
+                let distance: u32 = ck.key().decrypt_radix(&distance);
+                assert_eq!(distance, expected_hamming, "hamming {lhs} {rhs}");
+
+                for k in 0..4 {
+                    let result = sk.within_distance(&enc_lhs, &rhs_pat, k);
+                    let expected = expected_lev as usize <= k;
+
+                    assert_eq!(ck.key().decrypt_bool(&result), expected, "{lhs} {rhs} {k}");
+                }
+            }
+        }
+
+        // Only the first `max_len` chars are compared
+        let enc_lhs = FheString::new(&ck, "abcxyz", Some(3));
+        let rhs = GenericPattern::Enc(FheString::new(&ck, "abc", Some(1)));
+
+        let distance = sk.levenshtein(&enc_lhs, &rhs, 3);
+        let distance: u32 = ck.key().decrypt_radix(&distance);
+
+        assert_eq!(distance, 0);
+    }
+}
@@ -0,0 +1,354 @@
+use crate::ciphertext::{FheAsciiChar, FheString, GenericPattern};
+use crate::error::{check_no_null, FheStringError};
+use crate::server_key::{FheStringLen, ServerKey};
+use rayon::prelude::*;
+use tfhe::integer::RadixCiphertext;
+
+// Splits the template at the `{}` placeholders, returning the literal text around them (so there's
+// one more literal than placeholders). `{{` and `}}` are escaped braces, like in `format!`
+fn parse_template(template: &str) -> Result<Vec<String>, FheStringError> {
+    let error = |index, reason| FheStringError::InvalidTemplate { index, reason };
+
+    let mut literals = vec![String::new()];
+    let mut chars = template.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        match (c, chars.peek().map(|&(_, next)| next)) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                literals.last_mut().unwrap().push(c);
+            }
+            ('{', Some('}')) => {
+                chars.next();
+                literals.push(String::new());
+            }
+            ('{', _) => return Err(error(index, "expected `}` after `{`")),
+            ('}', _) => return Err(error(index, "unmatched `}`")),
+            _ => literals.last_mut().unwrap().push(c),
+        }
+    }
+
+    Ok(literals)
+}
+
+impl ServerKey {
+    // Concatenates all the parts, moving the nulls of the padded ones to the end at once. We lay
+    // out the chars of every part one after the other, and shift the chars that come after each
+    // padded part left by the number of nulls before them. As these shifts are independent they
+    // run in parallel, and we just have to combine the results
+    pub(super) fn concat_compact(&self, parts: &[&FheString]) -> FheString {
+        // Each group of chars ends with a padded part, except for the last one
+        let mut groups = vec![vec![]];
+        let mut padded_parts = vec![];
+
+        for part in parts {
+            groups.last_mut().unwrap().extend_from_slice(part.chars());
+
+            if part.is_padded() {
+                padded_parts.push(*part);
+                groups.push(vec![]);
+            }
+        }
+
+        let len: usize = groups.iter().map(Vec::len).sum();
+        let mut result = FheString::empty();
+
+        if padded_parts.is_empty() || len == 0 {
+            result.chars_vec().extend(groups.concat());
+            return result;
+        }
+
+        let null_counts: Vec<_> = padded_parts
+            .par_iter()
+            .map(|part| {
+                let FheStringLen::Padding(part_len) = self.byte_len(part) else {
+                    unreachable!("The part is padded")
+                };
+                let padded_len = self
+                    .key
+                    .create_trivial_radix(part.chars().len() as u32, self.index_blocks());
+
+                self.key.sub_parallelized(&padded_len, &part_len)
+            })
+            .collect();
+
+        // The first group isn't shifted, the next ones by the total nulls of the previous groups
+        let mut shifts: Vec<Option<RadixCiphertext>> = vec![None];
+        let mut total_nulls = self.key.create_trivial_zero_radix(self.index_blocks());
+        for null_count in null_counts {
+            self.key
+                .add_assign_parallelized(&mut total_nulls, &null_count);
+            shifts.push(Some(total_nulls.clone()));
+        }
+
+        // Each group at its position in a string with the length of the result
+        let mut placed_groups = vec![];
+        let mut offset = 0;
+        for (group, shift) in groups.into_iter().zip(shifts) {
+            if group.is_empty() {
+                continue;
+            }
+
+            let mut placed = FheString::empty();
+            let group_len = group.len();
+
+            placed.chars_vec().resize(offset, FheAsciiChar::null(self));
+            placed.chars_vec().extend(group);
+            placed.chars_vec().resize(len, FheAsciiChar::null(self));
+
+            placed_groups.push((placed, shift));
+            offset += group_len;
+        }
+
+        let uint = placed_groups
+            .into_par_iter()
+            .map(|(placed, shift)| match shift {
+                Some(shift) => self.left_shift_chars(&placed, &shift).into_uint(self),
+                None => placed.into_uint(self),
+            })
+            .reduce_with(|lhs, rhs| self.key.bitor_parallelized(&lhs, &rhs))
+            .expect("There's at least one non empty group");
+
+        result = FheString::from_uint(uint);
+        result.set_is_padded(true);
+
+        result
+    }
+
+    /// Returns a new encrypted string built from a clear template, in which each `{}`
+    /// placeholder is replaced by the next encrypted argument, like `format!`. `{{` and `}}` are
+    /// replaced by `{` and `}`.
+    ///
+    /// This is faster than building the same string with [`ServerKey::concat`]: each `concat`
+    /// with a padded lhs takes two encrypted shifts of the whole string, one after the other. Here
+    /// the nulls of all the padded arguments are moved to the end in a single pass, with at most
+    /// one shift per padded argument and all of them running in parallel.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the template is malformed, contains null characters "\0", or
+    /// if the number of placeholders is different from the number of arguments. See
+    /// [`ServerKey::try_format`] for the fallible version.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    ///
+    /// let enc_name = FheString::new(&ck, "Alice", Some(3));
+    /// let enc_count = sk.from_uint_decimal(ck.encrypt_u16(12, None).cipher(), 0);
+    ///
+    /// let result = sk.format("Hi {}, you have {} new messages", &[&enc_name, &enc_count]);
+    ///
+    /// assert_eq!(ck.decrypt_ascii(&result), "Hi Alice, you have 12 new messages");
+    /// ```
+    pub fn format(&self, template: &str, args: &[&FheString]) -> FheString {
+        self.try_format(template, args)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Returns a new encrypted string built from a clear template, in which each `{}`
+    /// placeholder is replaced by the next encrypted argument. See [`ServerKey::format`].
+    ///
+    /// Returns an error if the template is malformed, contains null characters "\0", or if the
+    /// number of placeholders is different from the number of arguments.
+    pub fn try_format(
+        &self,
+        template: &str,
+        args: &[&FheString],
+    ) -> Result<FheString, FheStringError> {
+        check_no_null(template)?;
+
+        let literals = parse_template(template)?;
+        if literals.len() != args.len() + 1 {
+            return Err(FheStringError::FormatArgsMismatch {
+                placeholders: literals.len() - 1,
+                args: args.len(),
+            });
+        }
+
+        let literals: Vec<_> = literals
+            .iter()
+            .map(|literal| FheString::trivial(self, literal))
+            .collect();
+
+        let mut parts = vec![&literals[0]];
+        for (arg, literal) in args.iter().zip(&literals[1..]) {
+            parts.extend([*arg, literal]);
+        }
+
+        Ok(self.concat_compact(&parts))
+    }
+
+    /// Returns a new encrypted string with all the encrypted parts concatenated, placing the
+    /// given separator (either encrypted or clear) between each of them, like `slice::join`.
+    ///
+    /// The nulls of the padded parts are moved to the end in a single pass, like in
+    /// [`ServerKey::format`], instead of once per part as when chaining [`ServerKey::concat`].
+    /// This allows to reassemble the parts returned by the split iterators after transforming
+    /// them.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    ///
+    /// let parts: Vec<_> = ["a", "b", "c"]
+    ///     .iter()
+    ///     .map(|s| FheString::new(&ck, s, Some(1)))
+    ///     .collect();
+    /// let sep = GenericPattern::Clear(ClearString::new(", ".to_string()));
+    ///
+    /// let result = sk.join(&parts, &sep);
+    ///
+    /// assert_eq!(ck.decrypt_ascii(&result), "a, b, c");
+    /// ```
+    pub fn join(&self, parts: &[FheString], sep: &GenericPattern) -> FheString {
+        let trivial_sep;
+        let sep = match sep {
+            GenericPattern::Clear(sep) => {
+                trivial_sep = FheString::trivial(self, sep.str());
+                &trivial_sep
+            }
+            GenericPattern::Enc(sep) => sep,
+        };
+
+        let mut all_parts = vec![];
+        for (i, part) in parts.iter().enumerate() {
+            if i > 0 {
+                all_parts.push(sep);
+            }
+            all_parts.push(part);
+        }
+
+        self.concat_compact(&all_parts)
+    }
+
+    /// Returns a new encrypted string with all the encrypted parts concatenated, like
+    /// `slice::concat`. See [`ServerKey::join`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    ///
+    /// let parts = [
+    ///     FheString::new(&ck, "Hello", Some(2)),
+    ///     FheString::new(&ck, ", ", None),
+    ///     FheString::new(&ck, "world!", Some(1)),
+    /// ];
+    ///
+    /// let result = sk.concat_all(&parts);
+    ///
+    /// assert_eq!(ck.decrypt_ascii(&result), "Hello, world!");
+    /// ```
+    pub fn concat_all(&self, parts: &[FheString]) -> FheString {
+        let parts: Vec<_> = parts.iter().collect();
+
+        self.concat_compact(&parts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_template;
+    use crate::ciphertext::{ClearString, FheString, GenericPattern};
+    use crate::error::FheStringError;
+    use crate::server_key::gen_keys;
+
+    #[test]
+    fn test_parse_template() {
+        let literals = parse_template("a{}b{{c}}{}").unwrap();
+        assert_eq!(literals, ["a", "b{c}", ""]);
+
+        let invalid = [
+            ("{", 0, "expected `}` after `{`"),
+            ("ab}", 2, "unmatched `}`"),
+            ("{}{x}", 2, "expected `}` after `{`"),
+            ("{}}", 2, "unmatched `}`"),
+        ];
+
+        for (template, index, reason) in invalid {
+            let err = parse_template(template).err();
+            assert_eq!(err, Some(FheStringError::InvalidTemplate { index, reason }));
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        let (ck, sk) = gen_keys();
+
+        // (template, args with their padding, expected)
+        let test_cases: [(&str, &[(&str, u32)], &str); 6] = [
+            ("", &[], ""),
+            ("no args", &[], "no args"),
+            ("{}", &[("abc", 2)], "abc"),
+            ("{}{}", &[("", 1), ("", 0)], ""),
+            (
+                "Hi {}, {} and {}!",
+                &[("Ann", 1), ("Bob", 0), ("Carl", 3)],
+                "Hi Ann, Bob and Carl!",
+            ),
+            ("{{{}}}: {}", &[("x", 0), ("", 2)], "{x}: "),
+        ];
+
+        for (template, args, expected) in test_cases {
+            let enc_args: Vec<_> = args
+                .iter()
+                .map(|(arg, pad)| FheString::new(&ck, arg, Some(*pad)))
+                .collect();
+            let enc_args: Vec<_> = enc_args.iter().collect();
+
+            let result = sk.format(template, &enc_args);
+            assert_eq!(ck.decrypt_ascii(&result), expected, "{template}");
+        }
+
+        let enc_arg = FheString::new(&ck, "a", None);
+
+        let err = sk.try_format("{} {}", &[&enc_arg]).err();
+        let expected = FheStringError::FormatArgsMismatch {
+            placeholders: 2,
+            args: 1,
+        };
+        assert_eq!(err, Some(expected));
+
+        let err = sk.try_format("a\0{}", &[&enc_arg]).err();
+        assert_eq!(err, Some(FheStringError::ContainsNull));
+    }
+
+    #[test]
+    fn test_join() {
+        let (ck, sk) = gen_keys();
+
+        // (parts with their padding, separator, padding)
+        let test_cases: [(&[(&str, u32)], &str, u32); 5] = [
+            (&[], ", ", 0),
+            (&[("", 2)], "-", 1),
+            (&[("a", 0), ("", 1), ("bc", 2)], "", 2),
+            (&[("one", 1), ("two", 0), ("three", 3)], " ", 0),
+            (&[("x", 2), ("y", 2)], "--", 3),
+        ];
+
+        for (parts, sep, sep_pad) in test_cases {
+            let enc_parts: Vec<_> = parts
+                .iter()
+                .map(|(part, pad)| FheString::new(&ck, part, Some(*pad)))
+                .collect();
+            let clear_parts: Vec<_> = parts.iter().map(|(part, _)| *part).collect();
+
+            let clear_sep = GenericPattern::Clear(ClearString::new(sep.to_string()));
+            let enc_sep = GenericPattern::Enc(FheString::new(&ck, sep, Some(sep_pad)));
+
+            for sep_arg in [clear_sep, enc_sep] {
+                let result = sk.join(&enc_parts, &sep_arg);
+                assert_eq!(ck.decrypt_ascii(&result), clear_parts.join(sep), "{sep}");
+            }
+
+            let result = sk.concat_all(&enc_parts);
+            assert_eq!(ck.decrypt_ascii(&result), clear_parts.concat());
+        }
+    }
+}
@@ -1,15 +1,39 @@
 mod comp;
+mod distance;
+mod format;
 mod no_patterns;
+mod numeric;
 mod pattern;
+mod resize;
+mod sanitize;
+mod sort;
+mod substring;
 mod trim;
+mod utf8;
 
-use crate::ciphertext::{FheAsciiChar, FheString};
+pub use pattern::{
+    MatchIndices, Matches, RMatchIndices, RSplit, RSplitN, RSplitTerminator, Split, SplitInclusive,
+    SplitN, SplitTerminator,
+};
+pub use resize::LengthBucket;
+pub use trim::SplitAsciiWhitespace;
+
+use crate::ciphertext::{FheAsciiChar, FheString, UIntArg};
 use crate::client_key::ClientKey;
+use crate::error::{check_ascii, check_no_null, FheStringError};
+use crate::parameters::{num_blocks, FheStringParameters, CHAR_BITS, INDEX_BITS, U16_BITS};
 use crate::N;
 use rayon::prelude::*;
 use std::cmp::Ordering;
 use tfhe::integer::bigint::static_unsigned::StaticUnsignedBigInt;
-use tfhe::integer::{BooleanBlock, IntegerCiphertext, RadixCiphertext, ServerKey as FheServerKey};
+use tfhe::integer::{
+    BooleanBlock, CompressedServerKey as FheCompressedServerKey, IntegerCiphertext,
+    RadixCiphertext, ServerKey as FheServerKey,
+};
+
+// Length in u64s of the clear UInts built from `ClearString` patterns. Each char takes up to 9 bits
+// in the UInt (with 3 message bits per block), so the `N` u64s that fit 8 bit chars aren't enough
+const CLEAR_UINT_LEN: usize = N * 2;
 
 /// Represents a server key to operate homomorphically on [`FheString`].
 #[derive(serde::Serialize, serde::Deserialize, Clone)]
@@ -17,13 +41,74 @@ pub struct ServerKey {
     key: FheServerKey,
 }
 
+/// Represents a compressed [`ServerKey`], which is much smaller to store or send to the server.
+///
+/// It has to be decompressed before operating on encrypted strings.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct CompressedServerKey {
+    key: FheCompressedServerKey,
+}
+
 pub fn gen_keys() -> (ClientKey, ServerKey) {
-    let ck = ClientKey::new();
+    gen_keys_with_parameters(FheStringParameters::default())
+}
+
+/// Generates a [`ClientKey`] and a [`ServerKey`] with the provided [`FheStringParameters`].
+///
+/// # Examples
+///
+/// ```no_run
+/// # use fhe_strings::*;
+/// let (ck, sk) = gen_keys_with_parameters(FheStringParameters::Message3Carry3);
+///
+/// let enc_s = FheString::new(&ck, "hello", Some(1));
+/// let result = sk.to_uppercase(&enc_s);
+///
+/// assert_eq!(enc_s.chars()[0].ciphertext().blocks().len(), 3);
+/// assert_eq!(ck.decrypt_ascii(&result), "HELLO");
+/// ```
+pub fn gen_keys_with_parameters(params: FheStringParameters) -> (ClientKey, ServerKey) {
+    let ck = ClientKey::with_parameters(params);
     let sk = ServerKey::new(&ck);
 
     (ck, sk)
 }
 
+/// Generates a [`ClientKey`] and a [`CompressedServerKey`], to be sent to the server and
+/// decompressed with [`CompressedServerKey::decompress`].
+pub fn gen_keys_compressed() -> (ClientKey, CompressedServerKey) {
+    gen_keys_compressed_with_parameters(FheStringParameters::default())
+}
+
+/// Generates a [`ClientKey`] and a [`CompressedServerKey`] with the provided
+/// [`FheStringParameters`]. See [`gen_keys_compressed`].
+pub fn gen_keys_compressed_with_parameters(
+    params: FheStringParameters,
+) -> (ClientKey, CompressedServerKey) {
+    let ck = ClientKey::with_parameters(params);
+    let compressed_sk = CompressedServerKey::new(&ck);
+
+    (ck, compressed_sk)
+}
+
+impl CompressedServerKey {
+    pub fn new(from: &ClientKey) -> Self {
+        Self {
+            key: FheCompressedServerKey::new_radix_compressed_server_key(from.key()),
+        }
+    }
+
+    pub fn key(&self) -> &FheCompressedServerKey {
+        &self.key
+    }
+
+    pub fn decompress(&self) -> ServerKey {
+        ServerKey {
+            key: FheServerKey::from(self.key.clone()),
+        }
+    }
+}
+
 impl ServerKey {
     pub fn new(from: &ClientKey) -> Self {
         Self {
@@ -35,12 +120,69 @@ impl ServerKey {
         &self.key
     }
 
+    // Number of blocks of each encrypted char, which depends on the parameters of the key
+    pub(crate) fn char_blocks(&self) -> usize {
+        num_blocks(CHAR_BITS, self.key.message_modulus())
+    }
+
+    // Number of blocks of the encrypted indices and lengths returned by the string functions
+    pub(crate) fn index_blocks(&self) -> usize {
+        num_blocks(INDEX_BITS, self.key.message_modulus())
+    }
+
+    // Number of blocks of the encrypted u16s, see `ClientKey::encrypt_u16`
+    pub(crate) fn u16_blocks(&self) -> usize {
+        num_blocks(U16_BITS, self.key.message_modulus())
+    }
+
+    // Number of bits that each char takes in the UInt representation of a string. It's greater than
+    // 8 when the message bits of the blocks don't divide 8 (e.g. 9 bits with 3 bits per block)
+    fn char_uint_bits(&self) -> usize {
+        self.char_blocks() * self.key.message_modulus().0.ilog2() as usize
+    }
+
+    /// # Panics
+    ///
+    /// This function will panic if the provided string is not ASCII or contains null characters
+    /// "\0". See [`ServerKey::try_trivial_encrypt_ascii`] for the fallible version.
     pub fn trivial_encrypt_ascii(&self, str: &str) -> TrivialEncryptOutput {
-        assert!(str.is_ascii() & !str.contains('\0'));
+        self.try_trivial_encrypt_ascii(str)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Returns an error if the provided string is not ASCII or contains null characters "\0".
+    pub fn try_trivial_encrypt_ascii(
+        &self,
+        str: &str,
+    ) -> Result<TrivialEncryptOutput, FheStringError> {
+        check_ascii(str)?;
+
+        Ok(self.trivial_encrypt_bytes(str.as_bytes()))
+    }
+
+    /// # Panics
+    ///
+    /// This function will panic if the provided string contains null characters "\0". See
+    /// [`ServerKey::try_trivial_encrypt_utf8`] for the fallible version.
+    pub fn trivial_encrypt_utf8(&self, str: &str) -> TrivialEncryptOutput {
+        self.try_trivial_encrypt_utf8(str)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Returns an error if the provided string contains null characters "\0".
+    pub fn try_trivial_encrypt_utf8(
+        &self,
+        str: &str,
+    ) -> Result<TrivialEncryptOutput, FheStringError> {
+        check_no_null(str)?;
+
+        Ok(self.trivial_encrypt_bytes(str.as_bytes()))
+    }
 
-        let enc_chars: Vec<_> = str
-            .bytes()
-            .map(|char| self.key.create_trivial_radix(char, 4))
+    fn trivial_encrypt_bytes(&self, bytes: &[u8]) -> TrivialEncryptOutput {
+        let enc_chars: Vec<_> = bytes
+            .iter()
+            .map(|byte| self.key.create_trivial_radix(*byte, self.char_blocks()))
             .collect();
 
         TrivialEncryptOutput { output: enc_chars }
@@ -57,18 +199,236 @@ impl TrivialEncryptOutput {
     }
 }
 
-// With no padding, the length is just the vector's length (clear result). With padding it requires
-// homomorphically counting the non zero elements (encrypted result).
+// With no padding, the length in bytes is just the vector's length (clear result). With padding it
+// requires homomorphically counting the bytes (encrypted result).
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(into = "VersionedFheStringLen", from = "VersionedFheStringLen")]
 pub enum FheStringLen {
     NoPadding(usize),
     Padding(RadixCiphertext),
 }
 
+// The number of UTF-8 chars is only clear for the empty string, otherwise it requires
+// homomorphically counting the leading bytes (encrypted result) even if there's no padding.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(into = "VersionedFheStringCharLen", from = "VersionedFheStringCharLen")]
+pub enum FheStringCharLen {
+    Clear(usize),
+    Enc(RadixCiphertext),
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(into = "VersionedFheStringIsEmpty", from = "VersionedFheStringIsEmpty")]
 pub enum FheStringIsEmpty {
     NoPadding(bool),
     Padding(BooleanBlock),
 }
 
+/// The encrypted result of [`ServerKey::cmp`]. Each of the two booleans tells if the lhs is less
+/// or greater than the rhs (if both are false they are equal), so they can be used directly in
+/// other homomorphic operations.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(into = "VersionedFheOrdering", from = "VersionedFheOrdering")]
+pub struct FheOrdering {
+    is_lt: BooleanBlock,
+    is_gt: BooleanBlock,
+}
+
+impl FheOrdering {
+    pub fn is_lt(&self) -> &BooleanBlock {
+        &self.is_lt
+    }
+
+    pub fn is_gt(&self) -> &BooleanBlock {
+        &self.is_gt
+    }
+
+    /// Returns `true` if the lhs and the rhs are equal, i.e. if neither is less than the other.
+    pub fn is_eq(&self, sk: &ServerKey) -> BooleanBlock {
+        let is_ne = sk.key.boolean_bitor(&self.is_lt, &self.is_gt);
+
+        sk.key.boolean_bitnot(&is_ne)
+    }
+}
+
+// Versioned serialized representations of the results above, see the ones in `ciphertext.rs`
+#[derive(serde::Serialize, serde::Deserialize)]
+enum VersionedFheStringLen {
+    V0(FheStringLenV0),
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+enum FheStringLenV0 {
+    NoPadding(usize),
+    Padding(RadixCiphertext),
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+enum VersionedFheStringCharLen {
+    V0(FheStringCharLenV0),
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+enum FheStringCharLenV0 {
+    Clear(usize),
+    Enc(RadixCiphertext),
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+enum VersionedFheStringIsEmpty {
+    V0(FheStringIsEmptyV0),
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+enum FheStringIsEmptyV0 {
+    NoPadding(bool),
+    Padding(BooleanBlock),
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+enum VersionedFheOrdering {
+    V0(FheOrderingV0),
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct FheOrderingV0 {
+    is_lt: BooleanBlock,
+    is_gt: BooleanBlock,
+}
+
+impl From<FheStringLen> for VersionedFheStringLen {
+    fn from(len: FheStringLen) -> Self {
+        let len = match len {
+            FheStringLen::NoPadding(len) => FheStringLenV0::NoPadding(len),
+            FheStringLen::Padding(len) => FheStringLenV0::Padding(len),
+        };
+
+        VersionedFheStringLen::V0(len)
+    }
+}
+
+impl From<VersionedFheStringLen> for FheStringLen {
+    fn from(versioned: VersionedFheStringLen) -> Self {
+        let VersionedFheStringLen::V0(len) = versioned;
+
+        match len {
+            FheStringLenV0::NoPadding(len) => FheStringLen::NoPadding(len),
+            FheStringLenV0::Padding(len) => FheStringLen::Padding(len),
+        }
+    }
+}
+
+impl From<FheStringCharLen> for VersionedFheStringCharLen {
+    fn from(len: FheStringCharLen) -> Self {
+        let len = match len {
+            FheStringCharLen::Clear(len) => FheStringCharLenV0::Clear(len),
+            FheStringCharLen::Enc(len) => FheStringCharLenV0::Enc(len),
+        };
+
+        VersionedFheStringCharLen::V0(len)
+    }
+}
+
+impl From<VersionedFheStringCharLen> for FheStringCharLen {
+    fn from(versioned: VersionedFheStringCharLen) -> Self {
+        let VersionedFheStringCharLen::V0(len) = versioned;
+
+        match len {
+            FheStringCharLenV0::Clear(len) => FheStringCharLen::Clear(len),
+            FheStringCharLenV0::Enc(len) => FheStringCharLen::Enc(len),
+        }
+    }
+}
+
+impl From<FheStringIsEmpty> for VersionedFheStringIsEmpty {
+    fn from(is_empty: FheStringIsEmpty) -> Self {
+        let is_empty = match is_empty {
+            FheStringIsEmpty::NoPadding(val) => FheStringIsEmptyV0::NoPadding(val),
+            FheStringIsEmpty::Padding(val) => FheStringIsEmptyV0::Padding(val),
+        };
+
+        VersionedFheStringIsEmpty::V0(is_empty)
+    }
+}
+
+impl From<VersionedFheStringIsEmpty> for FheStringIsEmpty {
+    fn from(versioned: VersionedFheStringIsEmpty) -> Self {
+        let VersionedFheStringIsEmpty::V0(is_empty) = versioned;
+
+        match is_empty {
+            FheStringIsEmptyV0::NoPadding(val) => FheStringIsEmpty::NoPadding(val),
+            FheStringIsEmptyV0::Padding(val) => FheStringIsEmpty::Padding(val),
+        }
+    }
+}
+
+impl From<FheOrdering> for VersionedFheOrdering {
+    fn from(ordering: FheOrdering) -> Self {
+        VersionedFheOrdering::V0(FheOrderingV0 {
+            is_lt: ordering.is_lt,
+            is_gt: ordering.is_gt,
+        })
+    }
+}
+
+impl From<VersionedFheOrdering> for FheOrdering {
+    fn from(versioned: VersionedFheOrdering) -> Self {
+        let VersionedFheOrdering::V0(ordering) = versioned;
+
+        FheOrdering {
+            is_lt: ordering.is_lt,
+            is_gt: ordering.is_gt,
+        }
+    }
+}
+
+// A boolean that may be known in the clear, for which we can skip the homomorphic operations
+#[derive(Clone)]
+enum MaybeClear {
+    Clear(bool),
+    Cipher(BooleanBlock),
+}
+
+impl MaybeClear {
+    fn into_boolean_block(self, sk: &ServerKey) -> BooleanBlock {
+        match self {
+            MaybeClear::Clear(bool) => sk.key.create_trivial_boolean_block(bool),
+            MaybeClear::Cipher(bool) => bool,
+        }
+    }
+}
+
+impl ServerKey {
+    fn maybe_clear_and(&self, a: &MaybeClear, b: &MaybeClear) -> MaybeClear {
+        match (a, b) {
+            (MaybeClear::Clear(false), _) | (_, MaybeClear::Clear(false)) => {
+                MaybeClear::Clear(false)
+            }
+            (MaybeClear::Clear(true), other) | (other, MaybeClear::Clear(true)) => other.clone(),
+            (MaybeClear::Cipher(a), MaybeClear::Cipher(b)) => {
+                MaybeClear::Cipher(self.key.boolean_bitand(a, b))
+            }
+        }
+    }
+
+    fn maybe_clear_or(&self, a: &MaybeClear, b: &MaybeClear) -> MaybeClear {
+        match (a, b) {
+            (MaybeClear::Clear(true), _) | (_, MaybeClear::Clear(true)) => MaybeClear::Clear(true),
+            (MaybeClear::Clear(false), other) | (other, MaybeClear::Clear(false)) => other.clone(),
+            (MaybeClear::Cipher(a), MaybeClear::Cipher(b)) => {
+                MaybeClear::Cipher(self.key.boolean_bitor(a, b))
+            }
+        }
+    }
+
+    fn maybe_clear_not(&self, a: &MaybeClear) -> MaybeClear {
+        match a {
+            MaybeClear::Clear(bool) => MaybeClear::Clear(!bool),
+            MaybeClear::Cipher(bool) => MaybeClear::Cipher(self.key.boolean_bitnot(bool)),
+        }
+    }
+}
+
 // A few helper functions for the implementations
 impl ServerKey {
     // If an iterator is longer than the other, the "excess" characters are ignored. This function
@@ -107,18 +467,20 @@ impl ServerKey {
             .rev()
             .flat_map(|c| c.ciphertext().blocks().to_owned())
             .collect();
-        let mut clear_pat = pat;
+        // We work with the pattern bytes, as trimming it could split a multi-byte UTF-8 char
+        let mut clear_pat = pat.as_bytes();
 
+        let char_blocks = self.char_blocks();
         let str_block_len = blocks_str.len();
-        let pat_block_len = clear_pat.len() * 4;
+        let pat_block_len = clear_pat.len() * char_blocks;
 
         let mut uint_str = RadixCiphertext::from_blocks(blocks_str);
 
         // Trim the str or pat such that the exceeding bytes are removed
         match str_block_len.cmp(&pat_block_len) {
             Ordering::Less => {
-                // `str_block_len` is always a multiple of 4 as each char is 4 blocks
-                clear_pat = &clear_pat[..str_block_len / 4];
+                // `str_block_len` is always a multiple of `char_blocks`
+                clear_pat = &clear_pat[..str_block_len / char_blocks];
             }
             Ordering::Greater => {
                 let diff = str_block_len - pat_block_len;
@@ -165,20 +527,36 @@ impl ServerKey {
     fn pad_cipher_and_cleartext_lsb(
         &self,
         lhs: &mut RadixCiphertext,
-        rhs: &str,
-    ) -> StaticUnsignedBigInt<N> {
-        let mut rhs_bytes = rhs.as_bytes().to_vec();
+        rhs: &[u8],
+    ) -> StaticUnsignedBigInt<CLEAR_UINT_LEN> {
+        let mut rhs_bytes = rhs.to_vec();
 
-        // Resize rhs with nulls at the end such that it matches the N const u64 length (for the
-        // StaticUnsignedBigInt)
+        // Resize rhs with nulls at the end such that it has the max number of chars of a
+        // `ClearString`
         rhs_bytes.resize(N * 8, 0);
 
-        let mut rhs_clear_uint = StaticUnsignedBigInt::<N>::from(0u8);
-        rhs_clear_uint.copy_from_be_byte_slice(&rhs_bytes);
+        // Each char takes `char_bits` in the UInt (the first char being the most significant),
+        // which may not be a whole byte, so we pack the bits before building the clear UInt
+        let char_bits = self.char_uint_bits();
+        let mut bits = Vec::with_capacity(CLEAR_UINT_LEN * 64);
+        bits.resize(CLEAR_UINT_LEN * 64 - rhs_bytes.len() * char_bits, false);
+
+        for byte in rhs_bytes {
+            bits.extend((0..char_bits).rev().map(|i| i < 8 && (byte >> i) & 1 == 1));
+        }
+
+        let packed: Vec<u8> = bits
+            .chunks(8)
+            .map(|chunk| chunk.iter().fold(0, |acc, bit| acc << 1 | *bit as u8))
+            .collect();
+
+        let mut rhs_clear_uint = StaticUnsignedBigInt::<CLEAR_UINT_LEN>::from(0u8);
+        rhs_clear_uint.copy_from_be_byte_slice(&packed);
 
         // Also fill the lhs with null blocks at the end
-        if lhs.blocks().len() < N * 8 * 4 {
-            let diff = N * 8 * 4 - lhs.blocks().len();
+        let max_blocks = N * 8 * self.char_blocks();
+        if lhs.blocks().len() < max_blocks {
+            let diff = max_blocks - lhs.blocks().len();
             self.key
                 .extend_radix_with_trivial_zero_blocks_lsb_assign(lhs, diff);
         }
@@ -239,6 +617,89 @@ impl ServerKey {
         }
     }
 
+    // Converts a `UIntArg` into a `RadixCiphertext` with the blocks of an index, such that it can
+    // be used in operations with encrypted indices and lengths
+    fn uint_arg_to_index(&self, arg: &UIntArg) -> RadixCiphertext {
+        match arg {
+            UIntArg::Clear(val) => self
+                .key
+                .create_trivial_radix(*val as u32, self.index_blocks()),
+            UIntArg::Enc(enc) => {
+                let mut index = enc.cipher().clone();
+                self.pad_or_trim_ciphertext(&mut index, self.index_blocks());
+
+                index
+            }
+        }
+    }
+
+    // Returns the number of items that are `Some`
+    fn count_some(&self, items: &[(FheString, BooleanBlock)]) -> RadixCiphertext {
+        let is_some: Vec<_> = items
+            .par_iter()
+            .map(|(_, is_some)| is_some.clone().into_radix(self.index_blocks(), &self.key))
+            .collect();
+
+        self.key
+            .sum_ciphertexts_parallelized(is_some.iter())
+            .unwrap_or_else(|| self.key.create_trivial_zero_radix(self.index_blocks()))
+    }
+
+    // Given some positions of a string (in any order) and whether each one is set, returns the
+    // position of each of the first `max` set ones in that order, or zero if there are fewer set
+    // positions, along with whether it exists. The nth set position is the one that has `n` set
+    // positions before it, so after counting them we can find all the `max` positions in parallel
+    fn first_set_positions(
+        &self,
+        flags: &[(usize, BooleanBlock)],
+        max: usize,
+    ) -> Vec<(RadixCiphertext, BooleanBlock)> {
+        let mut set_before = self.key.create_trivial_zero_radix(self.index_blocks());
+        let ranks: Vec<_> = flags
+            .iter()
+            .map(|(_, is_set)| {
+                let rank = set_before.clone();
+                let is_set = is_set.clone().into_radix(self.index_blocks(), &self.key);
+                self.key.add_assign_parallelized(&mut set_before, &is_set);
+
+                rank
+            })
+            .collect();
+
+        let set_count = set_before;
+        let zero = self.key.create_trivial_zero_radix(self.index_blocks());
+
+        (0..max)
+            .into_par_iter()
+            .map(|n| {
+                let positions: Vec<_> = flags
+                    .par_iter()
+                    .zip(&ranks)
+                    .map(|((position, is_set), rank)| {
+                        let mut is_nth = self.key.scalar_eq_parallelized(rank, n as u32);
+                        self.key.boolean_bitand_assign(&mut is_nth, is_set);
+
+                        let position = self
+                            .key
+                            .create_trivial_radix(*position as u32, self.index_blocks());
+
+                        self.key
+                            .if_then_else_parallelized(&is_nth, &position, &zero)
+                    })
+                    .collect();
+
+                rayon::join(
+                    || {
+                        self.key
+                            .sum_ciphertexts_parallelized(positions.iter())
+                            .unwrap_or_else(|| zero.clone())
+                    },
+                    || self.key.scalar_gt_parallelized(&set_count, n as u32),
+                )
+            })
+            .collect()
+    }
+
     fn conditional_string(
         &self,
         condition: &BooleanBlock,
@@ -271,7 +732,8 @@ impl ServerKey {
 
     fn left_shift_chars(&self, str: &FheString, shift: &RadixCiphertext) -> FheString {
         let uint = str.to_uint(self);
-        let mut shift_bits = self.key.scalar_left_shift_parallelized(shift, 3);
+        let char_bits = self.char_uint_bits();
+        let mut shift_bits = self.key.scalar_mul_parallelized(shift, char_bits as u32);
 
         // `shift_bits` needs to have the same block len as `uint` for the tfhe-rs shift to work
         self.pad_or_trim_ciphertext(&mut shift_bits, uint.blocks().len());
@@ -280,7 +742,7 @@ impl ServerKey {
 
         // If the shifting amount is >= than the str length we get zero i.e. all chars are out of
         // range (instead of wrapping, which is the behavior of Rust and tfhe-rs)
-        let bit_len = (str.chars().len() * 8) as u32;
+        let bit_len = (str.chars().len() * char_bits) as u32;
         let shift_ge_than_str = self.key.scalar_ge_parallelized(&shift_bits, bit_len);
 
         let result = self.key.if_then_else_parallelized(
@@ -294,7 +756,8 @@ impl ServerKey {
 
     fn right_shift_chars(&self, str: &FheString, shift: &RadixCiphertext) -> FheString {
         let uint = str.to_uint(self);
-        let mut shift_bits = self.key.scalar_left_shift_parallelized(shift, 3);
+        let char_bits = self.char_uint_bits();
+        let mut shift_bits = self.key.scalar_mul_parallelized(shift, char_bits as u32);
 
         // `shift_bits` needs to have the same block len as `uint` for the tfhe-rs shift to work
         self.pad_or_trim_ciphertext(&mut shift_bits, uint.blocks().len());
@@ -303,7 +766,7 @@ impl ServerKey {
 
         // If the shifting amount is >= than the str length we get zero i.e. all chars are out of
         // range (instead of wrapping, which is the behavior of Rust and tfhe-rs)
-        let bit_len = (str.chars().len() * 8) as u32;
+        let bit_len = (str.chars().len() * char_bits) as u32;
         let shift_ge_than_str = self.key.scalar_ge_parallelized(&shift_bits, bit_len);
 
         let result = self.key.if_then_else_parallelized(
@@ -318,6 +781,38 @@ impl ServerKey {
 
 pub trait FheStringIterator {
     fn next(&mut self, sk: &ServerKey) -> (FheString, BooleanBlock);
+
+    /// Returns the next `max_items` items of the iterator, as returned by `next`, along with the
+    /// encrypted number of them that are `Some`.
+    ///
+    /// By default this calls `next` `max_items` times. The split iterators (including
+    /// `SplitAsciiWhitespace`) compute all the items in parallel instead, splitting at every match
+    /// of the pattern at once (as long as `next` hasn't been called before).
+    fn collect_bounded(
+        self,
+        sk: &ServerKey,
+        max_items: usize,
+    ) -> (Vec<(FheString, BooleanBlock)>, RadixCiphertext)
+    where
+        Self: Sized,
+    {
+        collect_sequential(self, sk, max_items)
+    }
+}
+
+fn collect_sequential<I: FheStringIterator>(
+    mut iter: I,
+    sk: &ServerKey,
+    max_items: usize,
+) -> (Vec<(FheString, BooleanBlock)>, RadixCiphertext) {
+    let items: Vec<_> = (0..max_items).map(|_| iter.next(sk)).collect();
+    let count = sk.count_some(&items);
+
+    (items, count)
+}
+
+pub trait FheIndexIterator {
+    fn next(&mut self, sk: &ServerKey) -> (RadixCiphertext, BooleanBlock);
 }
 
 #[derive(Clone)]
@@ -1,18 +1,54 @@
 use crate::ciphertext::{ClearString, FheString, GenericPattern, UIntArg};
-use crate::server_key::{FheStringIsEmpty, FheStringLen, ServerKey};
+use crate::server_key::utf8::latin1_to_lowercase;
+use crate::server_key::{FheStringCharLen, FheStringIsEmpty, FheStringLen, ServerKey};
 use rayon::prelude::*;
 use tfhe::integer::BooleanBlock;
 
 impl ServerKey {
-    /// Returns the length of an encrypted string as an `FheStringLen` enum.
+    /// Returns the number of chars of an encrypted string as an `FheStringCharLen` enum, like
+    /// `str::chars().count()`.
+    ///
+    /// Non ASCII chars take several bytes, so this counts every byte that isn't a null or a UTF-8
+    /// continuation byte. Hence the length is calculated homomorphically and returned as an
+    /// encrypted `RadixCiphertext`, even if the string has no padding (unless it's empty). Use
+    /// [`ServerKey::byte_len`] to get a clear length for ASCII strings without padding.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    /// let s = "Ñandú";
+    ///
+    /// let enc_s = FheString::new_utf8(&ck, &s, Some(2));
+    ///
+    /// let length = match sk.len(&enc_s) {
+    ///     FheStringCharLen::Clear(length) => length,
+    ///     FheStringCharLen::Enc(ciphertext) => ck.key().decrypt_radix::<u32>(&ciphertext) as usize,
+    /// };
+    /// assert_eq!(length, 5);
+    /// ```
+    pub fn len(&self, str: &FheString) -> FheStringCharLen {
+        if str.chars().is_empty() {
+            return FheStringCharLen::Clear(0);
+        }
+
+        FheStringCharLen::Enc(self.chars_count(str))
+    }
+
+    /// Returns the length in bytes of an encrypted string as an `FheStringLen` enum, like
+    /// `str::len`.
     ///
     /// If the encrypted string has no padding, the length is the clear length of the char vector.
     /// If there is padding, the length is calculated homomorphically and returned as an
     /// encrypted `RadixCiphertext`.
     ///
+    /// For ASCII strings this is also the number of chars, see [`ServerKey::len`] otherwise.
+    ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let s = "hello";
     /// let number_of_nulls = 3;
@@ -20,8 +56,8 @@ impl ServerKey {
     /// let enc_s_no_padding = FheString::new(&ck, &s, None);
     /// let enc_s_with_padding = FheString::new(&ck, &s, Some(number_of_nulls));
     ///
-    /// let result_no_padding = sk.len(&enc_s_no_padding);
-    /// let result_with_padding = sk.len(&enc_s_with_padding);
+    /// let result_no_padding = sk.byte_len(&enc_s_no_padding);
+    /// let result_with_padding = sk.byte_len(&enc_s_with_padding);
     ///
     /// match result_no_padding {
     ///     FheStringLen::NoPadding(length) => assert_eq!(length, 5),
@@ -37,14 +73,14 @@ impl ServerKey {
     ///     }
     /// }
     /// ```
-    pub fn len(&self, str: &FheString) -> FheStringLen {
+    pub fn byte_len(&self, str: &FheString) -> FheStringLen {
         if str.is_padded() {
             let non_zero_chars: Vec<_> = str
                 .chars()
                 .par_iter()
                 .map(|char| {
                     let bool = self.key.scalar_ne_parallelized(char.ciphertext(), 0u8);
-                    bool.into_radix(16, &self.key)
+                    bool.into_radix(self.index_blocks(), &self.key)
                 })
                 .collect();
 
@@ -68,7 +104,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let s = "";
     /// let number_of_nulls = 2;
@@ -110,9 +147,13 @@ impl ServerKey {
 
     /// Returns a new encrypted string with all characters converted to uppercase.
     ///
+    /// Both ASCII and Latin-1 Supplement letters (such as "é" or "ñ") are converted. Latin-1
+    /// letters without a single char uppercase form in Latin-1 ("ß" and "ÿ") are kept the same.
+    ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let s = "Hello World";
     ///
@@ -126,19 +167,9 @@ impl ServerKey {
     pub fn to_uppercase(&self, str: &FheString) -> FheString {
         let mut uppercase = str.clone();
 
-        // Returns 1 if the corresponding character is lowercase, 0 otherwise
-        let lowercase_chars: Vec<_> = str
-            .chars()
-            .par_iter()
-            .map(|char| {
-                let (ge_97, le_122) = rayon::join(
-                    || self.key.scalar_ge_parallelized(char.ciphertext(), 97u8),
-                    || self.key.scalar_le_parallelized(char.ciphertext(), 122u8),
-                );
-
-                self.key.boolean_bitand(&ge_97, &le_122)
-            })
-            .collect();
+        // Returns 1 if the corresponding character is lowercase, 0 otherwise. Latin-1 lowercase
+        // letters are encoded as 0xC3 followed by a byte in 0xA0..=0xBE (0xB7 is the division sign)
+        let lowercase_chars = self.case_change_mask(str, (97, 122), (0xA0, 0xBE), 0xB7);
 
         // Subtraction by 32 makes the character uppercase
         uppercase
@@ -147,7 +178,7 @@ impl ServerKey {
             .zip(lowercase_chars)
             .par_bridge()
             .for_each(|(char, is_lowercase)| {
-                let mut subtract = self.key.create_trivial_radix(32, 4);
+                let mut subtract = self.key.create_trivial_radix(32, self.char_blocks());
 
                 self.key
                     .mul_assign_parallelized(&mut subtract, &is_lowercase.into_radix(1, &self.key));
@@ -161,9 +192,12 @@ impl ServerKey {
 
     /// Returns a new encrypted string with all characters converted to lowercase.
     ///
+    /// Both ASCII and Latin-1 Supplement letters (such as "É" or "Ñ") are converted.
+    ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let s = "Hello World";
     ///
@@ -177,19 +211,10 @@ impl ServerKey {
     pub fn to_lowercase(&self, str: &FheString) -> FheString {
         let mut lowercase = str.clone();
 
-        // Returns 1 if the corresponding character is uppercase, 0 otherwise
-        let uppercase_chars: Vec<_> = str
-            .chars()
-            .par_iter()
-            .map(|char| {
-                let (ge_65, le_90) = rayon::join(
-                    || self.key.scalar_ge_parallelized(char.ciphertext(), 65u8),
-                    || self.key.scalar_le_parallelized(char.ciphertext(), 90u8),
-                );
-
-                self.key.boolean_bitand(&ge_65, &le_90)
-            })
-            .collect();
+        // Returns 1 if the corresponding character is uppercase, 0 otherwise. Latin-1 uppercase
+        // letters are encoded as 0xC3 followed by a byte in 0x80..=0x9E (0x97 is the multiplication
+        // sign)
+        let uppercase_chars = self.case_change_mask(str, (65, 90), (0x80, 0x9E), 0x97);
 
         // Addition by 32 makes the character lowercase
         lowercase
@@ -198,7 +223,7 @@ impl ServerKey {
             .zip(uppercase_chars)
             .par_bridge()
             .for_each(|(char, is_uppercase)| {
-                let mut add = self.key.create_trivial_radix(32, 4);
+                let mut add = self.key.create_trivial_radix(32, self.char_blocks());
 
                 self.key
                     .mul_assign_parallelized(&mut add, &is_uppercase.into_radix(1, &self.key));
@@ -220,7 +245,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (s1, s2) = ("Hello", "hello");
     ///
@@ -233,26 +259,38 @@ impl ServerKey {
     /// assert!(are_equal);
     /// ```
     pub fn eq_ignore_case(&self, lhs: &FheString, rhs: &GenericPattern) -> BooleanBlock {
-        let (lhs, rhs) = rayon::join(
-            || self.to_lowercase(lhs),
-            || match rhs {
-                GenericPattern::Clear(rhs) => {
-                    GenericPattern::Clear(ClearString::new(rhs.str().to_lowercase()))
-                }
-                GenericPattern::Enc(rhs) => GenericPattern::Enc(self.to_lowercase(rhs)),
-            },
-        );
+        let (lhs, rhs) = self.to_lowercase_with_pattern(lhs, rhs);
 
         self.eq(&lhs, &rhs)
     }
 
+    // Lowercases an encrypted string and a pattern, where a clear pattern is lowercased for free.
+    // Lowercasing keeps the length of every char, so the indices in both strings are the same
+    pub(crate) fn to_lowercase_with_pattern(
+        &self,
+        str: &FheString,
+        pat: &GenericPattern,
+    ) -> (FheString, GenericPattern) {
+        rayon::join(|| self.to_lowercase(str), || self.to_lowercase_pattern(pat))
+    }
+
+    pub(crate) fn to_lowercase_pattern(&self, pat: &GenericPattern) -> GenericPattern {
+        match pat {
+            GenericPattern::Clear(pat) => {
+                GenericPattern::Clear(ClearString::new(latin1_to_lowercase(pat.str())))
+            }
+            GenericPattern::Enc(pat) => GenericPattern::Enc(self.to_lowercase(pat)),
+        }
+    }
+
     /// Concatenates two encrypted strings and returns the result as a new encrypted string.
     ///
     /// This function is equivalent to using the `+` operator on standard strings.
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (lhs, rhs) = ("Hello, ", "world!");
     ///
@@ -267,7 +305,7 @@ impl ServerKey {
     pub fn concat(&self, lhs: &FheString, rhs: &FheString) -> FheString {
         let mut result = lhs.clone();
 
-        match self.len(lhs) {
+        match self.byte_len(lhs) {
             // No homomorphic operation required if the lhs is not padded
             FheStringLen::NoPadding(_) => {
                 result.chars_vec().extend_from_slice(rhs.chars());
@@ -277,7 +315,9 @@ impl ServerKey {
             // If lhs is padded we can shift it right such that all nulls move to the start, then
             // we append the rhs and shift it left again to move the nulls to the new end
             FheStringLen::Padding(len) => {
-                let padded_len = self.key.create_trivial_radix(lhs.chars().len() as u32, 16);
+                let padded_len = self
+                    .key
+                    .create_trivial_radix(lhs.chars().len() as u32, self.index_blocks());
                 let number_of_nulls = self.key.sub_parallelized(&padded_len, &len);
 
                 result = self.right_shift_chars(&result, &number_of_nulls);
@@ -300,7 +340,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let s = "hi";
     ///
@@ -0,0 +1,444 @@
+use crate::ciphertext::{FheAsciiChar, FheString};
+use crate::parameters::num_blocks;
+use crate::server_key::ServerKey;
+use rayon::prelude::*;
+use tfhe::integer::{BooleanBlock, RadixCiphertext};
+
+// The ASCII classification of a char that we need for parsing
+struct ParsedChar {
+    digit: RadixCiphertext,
+    is_digit: BooleanBlock,
+    // Only computed for padded strings
+    is_null: Option<BooleanBlock>,
+}
+
+impl ServerKey {
+    fn parse_char(&self, char: &FheAsciiChar, padded: bool) -> ParsedChar {
+        let ((ge_zero, le_nine), (digit, is_null)) = rayon::join(
+            || {
+                rayon::join(
+                    || self.key.scalar_ge_parallelized(char.ciphertext(), b'0'),
+                    || self.key.scalar_le_parallelized(char.ciphertext(), b'9'),
+                )
+            },
+            || {
+                rayon::join(
+                    || self.key.scalar_sub_parallelized(char.ciphertext(), b'0'),
+                    || padded.then(|| self.key.scalar_eq_parallelized(char.ciphertext(), 0u8)),
+                )
+            },
+        );
+
+        ParsedChar {
+            digit,
+            is_digit: self.key.boolean_bitand(&ge_zero, &le_nine),
+            is_null,
+        }
+    }
+
+    // Parses the decimal digits as an unsigned integer of `bits` bits, like `str::parse`, returning
+    // zero when the string isn't valid
+    fn parse_uint(&self, str: &FheString, bits: usize) -> (RadixCiphertext, BooleanBlock) {
+        let chars = str.chars();
+        let num_blocks = num_blocks(bits, self.key.message_modulus());
+        let max = (1u128 << bits) - 1;
+
+        let zero = self.key.create_trivial_zero_radix(num_blocks);
+        if chars.is_empty() {
+            return (zero, self.key.create_trivial_boolean_block(false));
+        }
+
+        let (parsed, is_plus): (Vec<_>, _) = rayon::join(
+            || {
+                chars
+                    .par_iter()
+                    .map(|char| self.parse_char(char, str.is_padded()))
+                    .collect()
+            },
+            || self.key.scalar_eq_parallelized(chars[0].ciphertext(), b'+'),
+        );
+
+        // Every char is a digit or a padding null, except for an optional leading `+`. As nulls
+        // are only at the end, there's at least one digit if the first non `+` char is a digit
+        let (valid_chars, has_digit) = rayon::join(
+            || {
+                let valid: Vec<_> = parsed
+                    .par_iter()
+                    .enumerate()
+                    .map(|(i, char)| {
+                        let mut valid = char.is_digit.clone();
+
+                        if let Some(is_null) = &char.is_null {
+                            self.key.boolean_bitor_assign(&mut valid, is_null);
+                        }
+                        if i == 0 {
+                            self.key.boolean_bitor_assign(&mut valid, &is_plus);
+                        }
+
+                        valid
+                    })
+                    .collect();
+
+                // There's at least one char as we have handled the empty case
+                valid
+                    .into_iter()
+                    .reduce(|a, b| self.key.boolean_bitand(&a, &b))
+                    .unwrap()
+            },
+            || match parsed.get(1) {
+                Some(second) => {
+                    let plus_digit = self.key.boolean_bitand(&is_plus, &second.is_digit);
+                    self.key.boolean_bitor(&parsed[0].is_digit, &plus_digit)
+                }
+                None => parsed[0].is_digit.clone(),
+            },
+        );
+
+        // We accumulate with 4 extra bits, and saturate at `max + 1` after each digit, so that
+        // multiplying by 10 and adding a digit never wraps around
+        let acc_blocks = num_blocks(bits + 4, self.key.message_modulus());
+        let mut acc = self.key.create_trivial_zero_radix(acc_blocks);
+
+        for (i, char) in parsed.into_iter().enumerate() {
+            let mut digit = char.digit;
+            self.pad_or_trim_ciphertext(&mut digit, acc_blocks);
+
+            // The leading `+` doesn't add a digit
+            if i == 0 {
+                digit = self.key.if_then_else_parallelized(
+                    &is_plus,
+                    &self.key.create_trivial_zero_radix(acc_blocks),
+                    &digit,
+                );
+            }
+
+            let mut next = self.key.scalar_mul_parallelized(&acc, 10u8);
+            self.key.add_assign_parallelized(&mut next, &digit);
+            next = self.key.scalar_min_parallelized(&next, max + 1);
+
+            // Padding nulls don't change the value
+            acc = match char.is_null {
+                Some(is_null) => self.key.if_then_else_parallelized(&is_null, &acc, &next),
+                None => next,
+            };
+        }
+
+        let no_overflow = self.key.scalar_le_parallelized(&acc, max);
+
+        let mut is_valid = self.key.boolean_bitand(&has_digit, &no_overflow);
+        self.key.boolean_bitand_assign(&mut is_valid, &valid_chars);
+
+        self.pad_or_trim_ciphertext(&mut acc, num_blocks);
+        let value = self.key.if_then_else_parallelized(&is_valid, &acc, &zero);
+
+        (value, is_valid)
+    }
+
+    /// Parses this encrypted string as a decimal `u16`, like `str::parse::<u16>`. Returns the
+    /// encrypted value and an encrypted `BooleanBlock` that is true if the string is valid.
+    ///
+    /// The string is valid if it's made of decimal digits, with an optional leading `+`, and the
+    /// value fits in a `u16`. Otherwise the returned value is zero. Padding nulls are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    ///
+    /// let enc_s = FheString::new(&ck, "+1234", Some(3));
+    /// let (value, is_valid) = sk.parse_u16(&enc_s);
+    ///
+    /// assert_eq!(ck.key().decrypt_radix::<u16>(&value), 1234);
+    /// assert!(ck.key().decrypt_bool(&is_valid));
+    ///
+    /// let enc_s = FheString::new(&ck, "65536", None);
+    /// let (_, is_valid) = sk.parse_u16(&enc_s);
+    ///
+    /// assert!(!ck.key().decrypt_bool(&is_valid)); // Overflow
+    /// ```
+    pub fn parse_u16(&self, str: &FheString) -> (RadixCiphertext, BooleanBlock) {
+        self.parse_uint(str, u16::BITS as usize)
+    }
+
+    /// Parses this encrypted string as a decimal `u32`, like `str::parse::<u32>`. See
+    /// [`ServerKey::parse_u16`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    ///
+    /// let enc_s = FheString::new(&ck, "4294967295", None);
+    /// let (value, is_valid) = sk.parse_u32(&enc_s);
+    ///
+    /// assert_eq!(ck.key().decrypt_radix::<u32>(&value), u32::MAX);
+    /// assert!(ck.key().decrypt_bool(&is_valid));
+    /// ```
+    pub fn parse_u32(&self, str: &FheString) -> (RadixCiphertext, BooleanBlock) {
+        self.parse_uint(str, u32::BITS as usize)
+    }
+
+    /// Parses this encrypted string as a decimal `u64`, like `str::parse::<u64>`. See
+    /// [`ServerKey::parse_u16`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    ///
+    /// let enc_s = FheString::new(&ck, "12a", Some(1));
+    /// let (value, is_valid) = sk.parse_u64(&enc_s);
+    ///
+    /// assert_eq!(ck.key().decrypt_radix::<u64>(&value), 0);
+    /// assert!(!ck.key().decrypt_bool(&is_valid));
+    /// ```
+    pub fn parse_u64(&self, str: &FheString) -> (RadixCiphertext, BooleanBlock) {
+        self.parse_uint(str, u64::BITS as usize)
+    }
+
+    // Builds a string from the digit chars (most significant first), padding with '0' to the left
+    // up to `min_width` chars and removing the other leading zeros (except the last digit). As the
+    // removed zeros are at the start we shift the string left by their number, like `trim_start`
+    // does, so that the nulls are at the end
+    fn digits_to_string(&self, digits: Vec<RadixCiphertext>, min_width: usize) -> FheString {
+        let zeros = min_width.saturating_sub(digits.len());
+        let zero = self.key.create_trivial_radix(b'0', self.char_blocks());
+
+        let mut str = FheString::empty();
+        for digit in std::iter::repeat_n(zero, zeros).chain(digits) {
+            let mut char = FheAsciiChar::null(self);
+            *char.ciphertext_mut() = digit;
+
+            str.chars_vec().push(char);
+        }
+
+        let removable = str.chars().len() - min_width.max(1);
+        if removable == 0 {
+            return str;
+        }
+
+        let is_zero: Vec<_> = str.chars()[..removable]
+            .par_iter()
+            .map(|char| self.key.scalar_eq_parallelized(char.ciphertext(), b'0'))
+            .collect();
+
+        // A zero is a leading zero if all the previous digits are zeros as well
+        let mut is_leading = self.key.create_trivial_boolean_block(true);
+        let leading_zeros: Vec<_> = is_zero
+            .into_iter()
+            .map(|is_zero| {
+                self.key.boolean_bitand_assign(&mut is_leading, &is_zero);
+                is_leading
+                    .clone()
+                    .into_radix(self.index_blocks(), &self.key)
+            })
+            .collect();
+
+        let shift = self
+            .key
+            .sum_ciphertexts_parallelized(leading_zeros.iter())
+            .expect("There's at least one removable digit");
+
+        let mut result = self.left_shift_chars(&str, &shift);
+        result.append_null(self);
+
+        result
+    }
+
+    /// Returns the decimal representation of an encrypted unsigned integer as an encrypted
+    /// string, like `format!("{value:0min_width$}")`.
+    ///
+    /// The value can have any number of blocks (up to 128 bits), for instance it can be the
+    /// ciphertext of an [`EncU16`](crate::EncU16) or an index returned by [`ServerKey::find`].
+    /// The result has at least `min_width` digits, padded with leading zeros, while the other
+    /// leading zeros are removed, leaving nulls at the end. Hence the result can be used as input
+    /// to other functions, for instance [`ServerKey::concat`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the value has more than 128 bits.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    ///
+    /// let enc_n = ck.encrypt_u16(123, None);
+    /// let enc_s = FheString::new(&ck, "Balance: ", None);
+    ///
+    /// let enc_balance = sk.from_uint_decimal(enc_n.cipher(), 0);
+    /// let result = sk.concat(&enc_s, &enc_balance);
+    ///
+    /// assert_eq!(ck.decrypt_ascii(&result), "Balance: 123");
+    ///
+    /// let result = sk.from_uint_decimal(enc_n.cipher(), 5);
+    ///
+    /// assert_eq!(ck.decrypt_ascii(&result), "00123");
+    /// ```
+    pub fn from_uint_decimal(&self, value: &RadixCiphertext, min_width: usize) -> FheString {
+        let bits = value.blocks().len() * self.key.message_modulus().0.ilog2() as usize;
+        assert!(bits <= 128, "The value should have at most 128 bits");
+
+        let max = u128::MAX.checked_shr(128 - bits as u32).unwrap_or(0);
+        let num_digits = max.checked_ilog10().map_or(1, |log| log as usize + 1);
+
+        // Each digit is computed independently as `(value / 10^k) % 10`
+        let digits = (0..num_digits)
+            .into_par_iter()
+            .rev()
+            .map(|k| {
+                let quotient = self
+                    .key
+                    .scalar_div_parallelized(value, 10u128.pow(k as u32));
+                let mut digit = self.key.scalar_rem_parallelized(&quotient, 10u8);
+
+                self.pad_or_trim_ciphertext(&mut digit, self.char_blocks());
+                self.key.scalar_add_parallelized(&digit, b'0')
+            })
+            .collect();
+
+        self.digits_to_string(digits, min_width)
+    }
+
+    /// Returns the lowercase hexadecimal representation of an encrypted unsigned integer as an
+    /// encrypted string, like `format!("{value:0min_width$x}")`. See
+    /// [`ServerKey::from_uint_decimal`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    ///
+    /// let enc_n = ck.encrypt_u16(0xbeef, None);
+    ///
+    /// let result = sk.to_hex_string(enc_n.cipher(), 0);
+    /// assert_eq!(ck.decrypt_ascii(&result), "beef");
+    ///
+    /// let enc_n = ck.encrypt_u16(10, None);
+    ///
+    /// let result = sk.to_hex_string(enc_n.cipher(), 2);
+    /// assert_eq!(ck.decrypt_ascii(&result), "0a");
+    /// ```
+    pub fn to_hex_string(&self, value: &RadixCiphertext, min_width: usize) -> FheString {
+        let bits = value.blocks().len() * self.key.message_modulus().0.ilog2() as usize;
+        let num_digits = bits.div_ceil(4).max(1);
+
+        let digits = (0..num_digits)
+            .into_par_iter()
+            .rev()
+            .map(|k| {
+                let shifted = self
+                    .key
+                    .scalar_right_shift_parallelized(value, 4 * k as u32);
+                let mut nibble = self.key.scalar_bitand_parallelized(&shifted, 0xFu8);
+                self.pad_or_trim_ciphertext(&mut nibble, self.char_blocks());
+
+                let ((is_letter, letter), digit) = rayon::join(
+                    || {
+                        rayon::join(
+                            || self.key.scalar_gt_parallelized(&nibble, 9u8),
+                            || self.key.scalar_add_parallelized(&nibble, b'a' - 10),
+                        )
+                    },
+                    || self.key.scalar_add_parallelized(&nibble, b'0'),
+                );
+
+                self.key
+                    .if_then_else_parallelized(&is_letter, &letter, &digit)
+            })
+            .collect();
+
+        self.digits_to_string(digits, min_width)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ciphertext::FheString;
+    use crate::server_key::gen_keys;
+
+    #[test]
+    fn test_parse() {
+        let (ck, sk) = gen_keys();
+
+        let test_cases = [
+            "",
+            "0",
+            "+",
+            "+0",
+            "007",
+            "65535",
+            "65536",
+            "+65535",
+            "-1",
+            "1+",
+            "++1",
+            " 1",
+            "1 ",
+            "4294967295",
+            "4294967296",
+            "18446744073709551615",
+            "18446744073709551616",
+        ];
+
+        for str in test_cases {
+            for pad in [None, Some(2)] {
+                let enc_str = FheString::new(&ck, str, pad);
+
+                let (value, is_valid) = sk.parse_u16(&enc_str);
+                let result = ck
+                    .key()
+                    .decrypt_bool(&is_valid)
+                    .then(|| ck.key().decrypt_radix::<u16>(&value));
+                assert_eq!(result, str.parse::<u16>().ok(), "u16 {str}");
+
+                let (value, is_valid) = sk.parse_u32(&enc_str);
+                let result = ck
+                    .key()
+                    .decrypt_bool(&is_valid)
+                    .then(|| ck.key().decrypt_radix::<u32>(&value));
+                assert_eq!(result, str.parse::<u32>().ok(), "u32 {str}");
+
+                let (value, is_valid) = sk.parse_u64(&enc_str);
+                let result = ck
+                    .key()
+                    .decrypt_bool(&is_valid)
+                    .then(|| ck.key().decrypt_radix::<u64>(&value));
+                assert_eq!(result, str.parse::<u64>().ok(), "u64 {str}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_to_string() {
+        let (ck, sk) = gen_keys();
+
+        for value in [0u16, 7, 10, 255, 1234, 0xbeef, u16::MAX] {
+            let enc_value = ck.encrypt_u16(value, None);
+
+            for width in [0, 1, 3, 6] {
+                let result = sk.from_uint_decimal(enc_value.cipher(), width);
+                let expected = format!("{value:0width$}");
+                assert_eq!(ck.decrypt_ascii(&result), expected, "{value} {width}");
+
+                let result = sk.to_hex_string(enc_value.cipher(), width);
+                let expected = format!("{value:0width$x}");
+                assert_eq!(ck.decrypt_ascii(&result), expected, "{value:x} {width}");
+            }
+        }
+
+        // Any number of blocks, e.g. an encrypted index
+        let enc_value = sk.key().create_trivial_radix(u32::MAX, sk.index_blocks());
+
+        let result = sk.from_uint_decimal(&enc_value, 0);
+        assert_eq!(ck.decrypt_ascii(&result), u32::MAX.to_string());
+
+        let result = sk.to_hex_string(&enc_value, 0);
+        assert_eq!(ck.decrypt_ascii(&result), "ffffffff");
+    }
+}
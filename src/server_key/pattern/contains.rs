@@ -87,7 +87,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (bananas, nana, apples) = ("bananas", "nana", "apples");
     ///
@@ -144,7 +145,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (bananas, ba, nan) = ("bananas", "ba", "nan");
     ///
@@ -210,7 +212,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (bananas, anas, nana) = ("bananas", "anas", "nana");
     ///
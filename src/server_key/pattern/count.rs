@@ -0,0 +1,279 @@
+use crate::ciphertext::{FheAsciiChar, FheString, GenericPattern};
+use crate::server_key::pattern::IsMatch;
+use crate::server_key::{FheStringIsEmpty, FheStringLen, ServerKey};
+use rayon::prelude::*;
+use tfhe::integer::{BooleanBlock, RadixCiphertext};
+
+// Returns true if two occurrences of pat can overlap, i.e. if a non-empty proper prefix of pat is
+// also a suffix of it (like "aa" or "abab"). Otherwise, the overlapping and non-overlapping counts
+// are the same
+fn clear_pat_can_overlap(pat: &str) -> bool {
+    let bytes = pat.as_bytes();
+    let len = bytes.len();
+
+    (1..len).any(|i| bytes[..i] == bytes[len - i..])
+}
+
+impl ServerKey {
+    /// Returns the number of non-overlapping matches of the given pattern (either encrypted or
+    /// clear) in this encrypted string, as an encrypted `RadixCiphertext`. This is the equivalent
+    /// of `str::matches(pat).count()`.
+    ///
+    /// Matches are counted from the start of the string, and each one starts after the end of the
+    /// previous. As in `str::matches`, an empty pattern matches at every char boundary, so the
+    /// result is the number of chars plus one.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    /// let (s, pat) = ("banana", "ana");
+    ///
+    /// let enc_s = FheString::new(&ck, &s, Some(2));
+    /// let enc_pat = GenericPattern::Enc(FheString::new(&ck, &pat, None));
+    ///
+    /// let count = sk.count_matches(&enc_s, &enc_pat);
+    ///
+    /// assert_eq!(ck.key().decrypt_radix::<u32>(&count), 1);
+    /// ```
+    pub fn count_matches(&self, str: &FheString, pat: &GenericPattern) -> RadixCiphertext {
+        self.count_matches_internal(str, pat, false)
+    }
+
+    /// Returns the number of (possibly overlapping) matches of the given pattern (either encrypted
+    /// or clear) in this encrypted string, as an encrypted `RadixCiphertext`. That is, the number
+    /// of indices at which the pattern matches.
+    ///
+    /// As in [`ServerKey::count_matches`], an empty pattern matches at every char boundary.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    /// let (s, pat) = ("banana", "ana");
+    ///
+    /// let enc_s = FheString::new(&ck, &s, Some(2));
+    /// let enc_pat = GenericPattern::Clear(ClearString::new(pat.to_string()));
+    ///
+    /// let count = sk.count_matches_overlapping(&enc_s, &enc_pat);
+    ///
+    /// assert_eq!(ck.key().decrypt_radix::<u32>(&count), 2);
+    /// ```
+    pub fn count_matches_overlapping(
+        &self,
+        str: &FheString,
+        pat: &GenericPattern,
+    ) -> RadixCiphertext {
+        self.count_matches_internal(str, pat, true)
+    }
+
+    fn count_matches_internal(
+        &self,
+        str: &FheString,
+        pat: &GenericPattern,
+        overlapping: bool,
+    ) -> RadixCiphertext {
+        let trivial_or_enc_pat = match pat {
+            GenericPattern::Clear(pat) => FheString::trivial(self, pat.str()),
+            GenericPattern::Enc(pat) => pat.clone(),
+        };
+
+        // An empty pattern matches at every char boundary, including the end of str
+        let empty_pat_count = || {
+            let chars_count = self.chars_count(str);
+            self.key.scalar_add_parallelized(&chars_count, 1u32)
+        };
+
+        match self.length_checks(str, &trivial_or_enc_pat) {
+            IsMatch::Clear(true) => return empty_pat_count(),
+            IsMatch::Clear(false) => {
+                return self.key.create_trivial_zero_radix(self.index_blocks())
+            }
+
+            // This variant is only returned in the empty string case, in which there's one match if
+            // the pattern is empty
+            IsMatch::Cipher(val) => return val.into_radix(self.index_blocks(), &self.key),
+            IsMatch::None => (),
+        }
+
+        let counted: Vec<_> = self
+            .match_flags(str, pat, &trivial_or_enc_pat, overlapping, false)
+            .into_par_iter()
+            .map(|(_, is_counted)| is_counted.into_radix(self.index_blocks(), &self.key))
+            .collect();
+
+        let count = self
+            .key
+            .sum_ciphertexts_parallelized(counted.iter())
+            .unwrap_or_else(|| self.key.create_trivial_zero_radix(self.index_blocks()));
+
+        // A padded pattern may be empty, which is not covered by the shifted matches
+        match self.is_empty(&trivial_or_enc_pat) {
+            FheStringIsEmpty::Padding(is_empty) => {
+                self.key
+                    .if_then_else_parallelized(&is_empty, &empty_pat_count(), &count)
+            }
+            FheStringIsEmpty::NoPadding(_) => count,
+        }
+    }
+
+    // Returns whether the pattern matches at each of the indices of str at which it can match, in
+    // ascending order. Unless `overlapping`, only the non-overlapping matches are kept, which are
+    // found from the end if `reverse` (like `str::rmatches` does). This assumes that the
+    // `length_checks` didn't return early
+    pub(super) fn match_flags(
+        &self,
+        str: &FheString,
+        pat: &GenericPattern,
+        trivial_or_enc_pat: &FheString,
+        overlapping: bool,
+        reverse: bool,
+    ) -> Vec<(usize, BooleanBlock)> {
+        let ignore_pat_pad = trivial_or_enc_pat.is_padded();
+
+        let null = (!str.is_padded() && trivial_or_enc_pat.is_padded())
+            .then_some(FheAsciiChar::null(self));
+
+        let (str_iter, pat_iter, iter) =
+            self.contains_cases(str, trivial_or_enc_pat, null.as_ref());
+
+        let iter_values: Vec<_> = iter.collect();
+
+        let (matched, can_overlap) = match pat {
+            GenericPattern::Clear(pat) => (
+                self.clear_shifted_matches((str_iter, pat.str()), iter_values.into_par_iter()),
+                clear_pat_can_overlap(pat.str()),
+            ),
+            GenericPattern::Enc(_) => (
+                self.shifted_matches(
+                    (str_iter, pat_iter),
+                    iter_values.into_par_iter(),
+                    ignore_pat_pad,
+                ),
+                true,
+            ),
+        };
+
+        if overlapping || !can_overlap {
+            matched
+        } else {
+            self.non_overlapping_matches(matched, trivial_or_enc_pat, reverse)
+        }
+    }
+
+    // Given the matches at each index in ascending order, keeps only the ones that start at or
+    // after the end of the previous kept match, like `str::matches` does. If `reverse`, the matches
+    // are kept from the end, i.e. the ones that end before the start of the next kept match
+    fn non_overlapping_matches(
+        &self,
+        matched: Vec<(usize, BooleanBlock)>,
+        pat: &FheString,
+        reverse: bool,
+    ) -> Vec<(usize, BooleanBlock)> {
+        let pat_len = match self.byte_len(pat) {
+            FheStringLen::Padding(enc_val) => enc_val,
+            FheStringLen::NoPadding(val) => self
+                .key
+                .create_trivial_radix(val as u32, self.index_blocks()),
+        };
+
+        // The first index at which a match can be kept, or the index after which a match cannot
+        // end if `reverse`
+        let mut bound = if reverse {
+            self.key.create_trivial_radix(u32::MAX, self.index_blocks())
+        } else {
+            self.key.create_trivial_zero_radix(self.index_blocks())
+        };
+
+        let keep = |(i, is_matched): (usize, BooleanBlock)| {
+            let (no_overlap, new_bound) = if reverse {
+                let match_end = self.key.scalar_add_parallelized(&pat_len, i as u32);
+                let index = self.key.create_trivial_radix(i as u32, self.index_blocks());
+
+                (self.key.le_parallelized(&match_end, &bound), index)
+            } else {
+                rayon::join(
+                    || self.key.scalar_le_parallelized(&bound, i as u32),
+                    || self.key.scalar_add_parallelized(&pat_len, i as u32),
+                )
+            };
+
+            let is_kept = self.key.boolean_bitand(&is_matched, &no_overlap);
+
+            bound = self
+                .key
+                .if_then_else_parallelized(&is_kept, &new_bound, &bound);
+
+            (i, is_kept)
+        };
+
+        if reverse {
+            let mut kept: Vec<_> = matched.into_iter().rev().map(keep).collect();
+            kept.reverse();
+
+            kept
+        } else {
+            matched.into_iter().map(keep).collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::clear_pat_can_overlap;
+    use crate::ciphertext::{ClearString, FheString, GenericPattern};
+    use crate::server_key::gen_keys;
+
+    #[test]
+    fn test_count_matches() {
+        let (ck, sk) = gen_keys();
+
+        // (str, str padding, pat, pat padding)
+        let test_cases = [
+            ("", 0, "", 0),
+            ("", 2, "", 1),
+            ("", 1, "a", 0),
+            ("héllo", 0, "", 2),
+            ("aaaa", 0, "aa", 0),
+            ("aaaa", 1, "aa", 2),
+            ("banana", 2, "ana", 0),
+            ("abcabc", 0, "abc", 1),
+            ("abcabc", 1, "cab", 0),
+            ("abc", 0, "abcd", 1),
+        ];
+
+        for (str, str_pad, pat, pat_pad) in test_cases {
+            let enc_str = FheString::new_utf8(&ck, str, Some(str_pad));
+
+            let expected = str.matches(pat).count() as u32;
+            let expected_overlapping = if pat.is_empty() {
+                expected
+            } else {
+                (0..str.len())
+                    .filter(|&i| str.as_bytes()[i..].starts_with(pat.as_bytes()))
+                    .count() as u32
+            };
+
+            for enc_pat in [
+                GenericPattern::Clear(ClearString::new(pat.to_string())),
+                GenericPattern::Enc(FheString::new(&ck, pat, Some(pat_pad))),
+            ] {
+                let count = sk.count_matches(&enc_str, &enc_pat);
+                let overlapping = sk.count_matches_overlapping(&enc_str, &enc_pat);
+
+                assert_eq!(ck.key().decrypt_radix::<u32>(&count), expected);
+                assert_eq!(
+                    ck.key().decrypt_radix::<u32>(&overlapping),
+                    expected_overlapping
+                );
+            }
+        }
+
+        assert!(clear_pat_can_overlap("aa"));
+        assert!(clear_pat_can_overlap("abcab"));
+        assert!(!clear_pat_can_overlap("abc"));
+        assert!(!clear_pat_can_overlap("a"));
+    }
+}
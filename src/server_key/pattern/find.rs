@@ -7,19 +7,17 @@ use tfhe::integer::{BooleanBlock, RadixCiphertext};
 
 impl ServerKey {
     // Compare pat with str, with pat shifted right (in relation to str) the number of times given
-    // by iter. Returns the first character index of the last match, or the first character index
-    // of the first match if the range is reversed. If there's no match defaults to 0
-    fn compare_shifted_index(
+    // by iter. Returns each shift value along with whether pat matched at that index, in the same
+    // order as iter
+    pub(super) fn shifted_matches(
         &self,
         str_pat: (CharIter, CharIter),
         par_iter: IntoIter<usize>,
         ignore_pat_pad: bool,
-    ) -> (RadixCiphertext, BooleanBlock) {
-        let mut result = self.key.create_trivial_boolean_block(false);
-        let mut last_match_index = self.key.create_trivial_zero_radix(16);
+    ) -> Vec<(usize, BooleanBlock)> {
         let (str, pat) = str_pat;
 
-        let matched: Vec<_> = par_iter
+        par_iter
             .map(|start| {
                 let str_chars = str.clone().skip(start);
                 let pat_chars = pat.clone();
@@ -37,10 +35,25 @@ impl ServerKey {
 
                 (start, is_matched)
             })
-            .collect();
+            .collect()
+    }
+
+    // Compare pat with str, with pat shifted right (in relation to str) the number of times given
+    // by iter. Returns the first character index of the last match, or the first character index
+    // of the first match if the range is reversed. If there's no match defaults to 0
+    fn compare_shifted_index(
+        &self,
+        str_pat: (CharIter, CharIter),
+        par_iter: IntoIter<usize>,
+        ignore_pat_pad: bool,
+    ) -> (RadixCiphertext, BooleanBlock) {
+        let mut result = self.key.create_trivial_boolean_block(false);
+        let mut last_match_index = self.key.create_trivial_zero_radix(self.index_blocks());
+
+        let matched = self.shifted_matches(str_pat, par_iter, ignore_pat_pad);
 
         for (i, is_matched) in matched {
-            let index = self.key.create_trivial_radix(i as u32, 16);
+            let index = self.key.create_trivial_radix(i as u32, self.index_blocks());
 
             rayon::join(
                 || {
@@ -56,16 +69,14 @@ impl ServerKey {
         (last_match_index, result)
     }
 
-    fn clear_compare_shifted_index(
+    pub(super) fn clear_shifted_matches(
         &self,
         str_pat: (CharIter, &str),
         par_iter: IntoIter<usize>,
-    ) -> (RadixCiphertext, BooleanBlock) {
-        let mut result = self.key.create_trivial_boolean_block(false);
-        let mut last_match_index = self.key.create_trivial_zero_radix(16);
+    ) -> Vec<(usize, BooleanBlock)> {
         let (str, pat) = str_pat;
 
-        let matched: Vec<_> = par_iter
+        par_iter
             .map(|start| {
                 let str_chars = str.clone().skip(start);
 
@@ -74,10 +85,21 @@ impl ServerKey {
 
                 (start, is_matched)
             })
-            .collect();
+            .collect()
+    }
+
+    fn clear_compare_shifted_index(
+        &self,
+        str_pat: (CharIter, &str),
+        par_iter: IntoIter<usize>,
+    ) -> (RadixCiphertext, BooleanBlock) {
+        let mut result = self.key.create_trivial_boolean_block(false);
+        let mut last_match_index = self.key.create_trivial_zero_radix(self.index_blocks());
+
+        let matched = self.clear_shifted_matches(str_pat, par_iter);
 
         for (i, is_matched) in matched {
-            let index = self.key.create_trivial_radix(i as u32, 16);
+            let index = self.key.create_trivial_radix(i as u32, self.index_blocks());
 
             rayon::join(
                 || {
@@ -105,7 +127,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (haystack, needle) = ("hello world", "world");
     ///
@@ -126,7 +149,7 @@ impl ServerKey {
             GenericPattern::Enc(pat) => pat.clone(),
         };
 
-        let zero = self.key.create_trivial_zero_radix(16);
+        let zero = self.key.create_trivial_zero_radix(self.index_blocks());
         match self.length_checks(str, &trivial_or_enc_pat) {
             // bool is true if pattern is empty, in which the first match index is 0. If it's false
             // we default to 0 as well
@@ -171,7 +194,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (haystack, needle) = ("hello world world", "world");
     ///
@@ -192,16 +216,16 @@ impl ServerKey {
             GenericPattern::Enc(pat) => pat.clone(),
         };
 
-        let zero = self.key.create_trivial_zero_radix(16);
+        let zero = self.key.create_trivial_zero_radix(self.index_blocks());
         match self.length_checks(str, &trivial_or_enc_pat) {
             IsMatch::Clear(val) => {
                 // val = true if pattern is empty, in which the last match index = str.len()
                 let index = if val {
-                    match self.len(str) {
+                    match self.byte_len(str) {
                         FheStringLen::Padding(cipher_len) => cipher_len,
-                        FheStringLen::NoPadding(len) => {
-                            self.key.create_trivial_radix(len as u32, 16)
-                        }
+                        FheStringLen::NoPadding(len) => self
+                            .key
+                            .create_trivial_radix(len as u32, self.index_blocks()),
                     }
                 } else {
                     zero
@@ -251,11 +275,11 @@ impl ServerKey {
 
                 // The non padded str case was handled thanks to + 1 in the ext_iter
                 if str.is_padded() && padded_pat_is_empty.is_some() {
-                    let str_true_len = match self.len(str) {
+                    let str_true_len = match self.byte_len(str) {
                         FheStringLen::Padding(cipher_len) => cipher_len,
-                        FheStringLen::NoPadding(len) => {
-                            self.key.create_trivial_radix(len as u32, 16)
-                        }
+                        FheStringLen::NoPadding(len) => self
+                            .key
+                            .create_trivial_radix(len as u32, self.index_blocks()),
                     };
 
                     Some((padded_pat_is_empty.unwrap(), str_true_len))
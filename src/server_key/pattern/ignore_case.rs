@@ -0,0 +1,213 @@
+use crate::ciphertext::{FheString, GenericPattern};
+use crate::server_key::ServerKey;
+use tfhe::integer::{BooleanBlock, RadixCiphertext};
+
+// The case-insensitive variants lowercase the encrypted string once (and a clear pattern in the
+// clear), with the same rules as `ServerKey::to_lowercase`, and then run the usual algorithm
+impl ServerKey {
+    /// Returns `true` if the given pattern (either encrypted or clear) matches a substring of this
+    /// encrypted string, ignoring case differences.
+    ///
+    /// Both ASCII and Latin-1 Supplement letters are case folded, as in
+    /// [`ServerKey::to_lowercase`]. See [`ServerKey::contains`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    ///
+    /// let enc_s = FheString::new(&ck, "Hello World", Some(2));
+    /// let pat = GenericPattern::Clear(ClearString::new("O wOR".to_string()));
+    ///
+    /// let result = sk.contains_ignore_case(&enc_s, &pat);
+    /// assert!(ck.key().decrypt_bool(&result));
+    /// ```
+    pub fn contains_ignore_case(&self, str: &FheString, pat: &GenericPattern) -> BooleanBlock {
+        let (str, pat) = self.to_lowercase_with_pattern(str, pat);
+
+        self.contains(&str, &pat)
+    }
+
+    /// Returns `true` if the given pattern (either encrypted or clear) matches a prefix of this
+    /// encrypted string, ignoring case differences.
+    ///
+    /// Both ASCII and Latin-1 Supplement letters are case folded, as in
+    /// [`ServerKey::to_lowercase`]. See [`ServerKey::starts_with`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    ///
+    /// let enc_s = FheString::new(&ck, "Hello World", None);
+    /// let pat = GenericPattern::Enc(FheString::new(&ck, "HELLO", Some(1)));
+    ///
+    /// let result = sk.starts_with_ignore_case(&enc_s, &pat);
+    /// assert!(ck.key().decrypt_bool(&result));
+    /// ```
+    pub fn starts_with_ignore_case(&self, str: &FheString, pat: &GenericPattern) -> BooleanBlock {
+        let (str, pat) = self.to_lowercase_with_pattern(str, pat);
+
+        self.starts_with(&str, &pat)
+    }
+
+    /// Returns `true` if the given pattern (either encrypted or clear) matches a suffix of this
+    /// encrypted string, ignoring case differences.
+    ///
+    /// Both ASCII and Latin-1 Supplement letters are case folded, as in
+    /// [`ServerKey::to_lowercase`]. See [`ServerKey::ends_with`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    ///
+    /// let enc_s = FheString::new(&ck, "report.PDF", None);
+    /// let pat = GenericPattern::Clear(ClearString::new(".pdf".to_string()));
+    ///
+    /// let result = sk.ends_with_ignore_case(&enc_s, &pat);
+    /// assert!(ck.key().decrypt_bool(&result));
+    /// ```
+    pub fn ends_with_ignore_case(&self, str: &FheString, pat: &GenericPattern) -> BooleanBlock {
+        let (str, pat) = self.to_lowercase_with_pattern(str, pat);
+
+        self.ends_with(&str, &pat)
+    }
+
+    /// Returns a tuple containing the byte index of the first character of this encrypted string
+    /// that matches the given pattern (either encrypted or clear) ignoring case differences, and
+    /// a boolean indicating if a match was found.
+    ///
+    /// Both ASCII and Latin-1 Supplement letters are case folded, as in
+    /// [`ServerKey::to_lowercase`], which doesn't change their length in bytes, so the index is
+    /// also valid in the original string. See [`ServerKey::find`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    ///
+    /// let enc_s = FheString::new(&ck, "Hello World", None);
+    /// let pat = GenericPattern::Clear(ClearString::new("world".to_string()));
+    ///
+    /// let (index, found) = sk.find_ignore_case(&enc_s, &pat);
+    ///
+    /// let index = ck.key().decrypt_radix::<u32>(&index);
+    /// let found = ck.key().decrypt_bool(&found);
+    ///
+    /// assert!(found);
+    /// assert_eq!(index, 6);
+    /// ```
+    pub fn find_ignore_case(
+        &self,
+        str: &FheString,
+        pat: &GenericPattern,
+    ) -> (RadixCiphertext, BooleanBlock) {
+        let (str, pat) = self.to_lowercase_with_pattern(str, pat);
+
+        self.find(&str, &pat)
+    }
+
+    /// Returns a new encrypted string with all non-overlapping occurrences of a pattern (either
+    /// encrypted or clear) replaced by another specified encrypted pattern, where the occurrences
+    /// are found ignoring case differences.
+    ///
+    /// The chars that are not replaced keep their original case. Both ASCII and Latin-1
+    /// Supplement letters are case folded, as in [`ServerKey::to_lowercase`]. See
+    /// [`ServerKey::replace`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    ///
+    /// let enc_s = FheString::new(&ck, "Cats and CATS", None);
+    /// let from = GenericPattern::Clear(ClearString::new("cats".to_string()));
+    /// let enc_to = FheString::new(&ck, "Dogs", None);
+    ///
+    /// let result = sk.replace_ignore_case(&enc_s, &from, &enc_to);
+    ///
+    /// assert_eq!(ck.decrypt_ascii(&result), "Dogs and Dogs");
+    /// ```
+    pub fn replace_ignore_case(
+        &self,
+        str: &FheString,
+        from: &GenericPattern,
+        to: &FheString,
+    ) -> FheString {
+        let from = self.to_lowercase_pattern(from);
+
+        self.replace_all(str, &from, to, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ciphertext::{ClearString, FheString, GenericPattern};
+    use crate::server_key::gen_keys;
+
+    #[test]
+    fn test_ignore_case() {
+        let (ck, sk) = gen_keys();
+
+        // (str, padding, pattern, padding)
+        let test_cases = [
+            ("", 0, "", 1),
+            ("Hello", 1, "", 0),
+            ("Hello", 0, "hE", 1),
+            ("Hello", 2, "LO", 0),
+            ("heLLo wORLD", 1, "O w", 2),
+            ("ÉCOLE école", 0, "éc", 0),
+            ("Ñandú", 2, "ÑANDÚ", 1),
+            ("abc", 0, "x", 0),
+        ];
+
+        for (str, str_pad, pat, pat_pad) in test_cases {
+            let (lower_str, lower_pat) = (str.to_lowercase(), pat.to_lowercase());
+            let enc_str = FheString::new_utf8(&ck, str, Some(str_pad));
+            let enc_to = FheString::new(&ck, "-", Some(1));
+
+            let clear_pat = GenericPattern::Clear(ClearString::new(pat.to_string()));
+            let enc_pat = GenericPattern::Enc(FheString::new_utf8(&ck, pat, Some(pat_pad)));
+
+            for pat_arg in [clear_pat, enc_pat] {
+                let result = sk.contains_ignore_case(&enc_str, &pat_arg);
+                let expected = lower_str.contains(&lower_pat);
+                assert_eq!(ck.key().decrypt_bool(&result), expected, "{str} {pat}");
+
+                let result = sk.starts_with_ignore_case(&enc_str, &pat_arg);
+                let expected = lower_str.starts_with(&lower_pat);
+                assert_eq!(ck.key().decrypt_bool(&result), expected, "{str} {pat}");
+
+                let result = sk.ends_with_ignore_case(&enc_str, &pat_arg);
+                let expected = lower_str.ends_with(&lower_pat);
+                assert_eq!(ck.key().decrypt_bool(&result), expected, "{str} {pat}");
+
+                let (index, found) = sk.find_ignore_case(&enc_str, &pat_arg);
+                let expected = lower_str.find(&lower_pat);
+                let found = ck.key().decrypt_bool(&found).then(|| {
+                    let index: u32 = ck.key().decrypt_radix(&index);
+                    index as usize
+                });
+                assert_eq!(found, expected, "{str} {pat}");
+
+                // Replacing in the lowercased string gives the same indices as in the original
+                let result = sk.replace_ignore_case(&enc_str, &pat_arg, &enc_to);
+                let mut expected = String::new();
+                let mut last = 0;
+                for (start, part) in lower_str.match_indices(&lower_pat) {
+                    expected += &str[last..start];
+                    expected += "-";
+                    last = start + part.len();
+                }
+                expected += &str[last..];
+                assert_eq!(ck.decrypt_utf8(&result), expected, "{str} {pat}");
+            }
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use crate::ciphertext::{FheString, GenericPattern};
+use crate::server_key::{FheIndexIterator, FheStringIterator, FheStringLen, ServerKey};
+use tfhe::integer::{BooleanBlock, RadixCiphertext};
+
+pub struct MatchIndices {
+    internal: MatchIndicesInternal,
+}
+
+pub struct RMatchIndices {
+    internal: MatchIndicesInternal,
+}
+
+pub struct Matches {
+    internal: MatchIndicesInternal,
+}
+
+struct MatchIndicesInternal {
+    reverse: bool,
+    state: FheString,
+    pat: GenericPattern,
+    // Index of the `state` start in the original str (only used when not `reverse`, as in that
+    // case the state is a suffix of the original str)
+    offset: RadixCiphertext,
+    counter: u16,
+    max_counter: RadixCiphertext,
+    counter_lt_max: BooleanBlock,
+}
+
+impl ServerKey {
+    /// Creates an iterator over the non-overlapping matches of a pattern (either encrypted or
+    /// clear) in the encrypted string, yielding the index of each match.
+    ///
+    /// The iterator, of type `MatchIndices`, can be used to sequentially retrieve the indices.
+    /// Each call to `next` on the iterator returns a tuple with the next encrypted index (in bytes)
+    /// and a boolean indicating `Some` (true) or `None` (false).
+    ///
+    /// As in `str::match_indices`, an empty pattern matches at every char boundary, including the
+    /// end of the string.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    /// let (s, pat) = ("abcXXXabcYYYabc", "abc");
+    ///
+    /// let enc_s = FheString::new(&ck, &s, None);
+    /// let enc_pat = GenericPattern::Enc(FheString::new(&ck, &pat, None));
+    ///
+    /// let mut match_iter = sk.match_indices(&enc_s, &enc_pat);
+    /// let (first_index, first_is_some) = match_iter.next(&sk);
+    /// let (second_index, second_is_some) = match_iter.next(&sk);
+    /// let (third_index, third_is_some) = match_iter.next(&sk);
+    /// let (_, no_more_items) = match_iter.next(&sk);
+    ///
+    /// assert_eq!(ck.key().decrypt_radix::<u32>(&first_index), 0);
+    /// assert!(ck.key().decrypt_bool(&first_is_some));
+    /// assert_eq!(ck.key().decrypt_radix::<u32>(&second_index), 6);
+    /// assert!(ck.key().decrypt_bool(&second_is_some));
+    /// assert_eq!(ck.key().decrypt_radix::<u32>(&third_index), 12);
+    /// assert!(ck.key().decrypt_bool(&third_is_some));
+    /// assert!(!ck.key().decrypt_bool(&no_more_items));
+    /// ```
+    pub fn match_indices(&self, str: &FheString, pat: &GenericPattern) -> MatchIndices {
+        let internal = self.match_indices_internal(str, pat, false);
+
+        MatchIndices { internal }
+    }
+
+    /// Creates an iterator over the non-overlapping matches of a pattern (either encrypted or
+    /// clear) in the encrypted string, yielding the index of each match in reverse order.
+    ///
+    /// The iterator, of type `RMatchIndices`, can be used to sequentially retrieve the indices.
+    /// Each call to `next` on the iterator returns a tuple with the next encrypted index (in bytes)
+    /// and a boolean indicating `Some` (true) or `None` (false).
+    ///
+    /// As in `str::rmatch_indices`, the matches are found from the end, so with overlapping
+    /// occurrences the yielded indices may differ from the ones of [`ServerKey::match_indices`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    /// let (s, pat) = ("aaa", "aa");
+    ///
+    /// let enc_s = FheString::new(&ck, &s, Some(1));
+    /// let enc_pat = GenericPattern::Clear(ClearString::new(pat.to_string()));
+    ///
+    /// let mut rmatch_iter = sk.rmatch_indices(&enc_s, &enc_pat);
+    /// let (last_index, last_is_some) = rmatch_iter.next(&sk);
+    /// let (_, no_more_items) = rmatch_iter.next(&sk);
+    ///
+    /// assert_eq!(ck.key().decrypt_radix::<u32>(&last_index), 1);
+    /// assert!(ck.key().decrypt_bool(&last_is_some));
+    /// assert!(!ck.key().decrypt_bool(&no_more_items));
+    /// ```
+    pub fn rmatch_indices(&self, str: &FheString, pat: &GenericPattern) -> RMatchIndices {
+        let internal = self.match_indices_internal(str, pat, true);
+
+        RMatchIndices { internal }
+    }
+
+    /// Creates an iterator over the non-overlapping matches of a pattern (either encrypted or
+    /// clear) in the encrypted string, yielding each matched substring.
+    ///
+    /// The iterator, of type `Matches`, can be used to sequentially retrieve the matches. Each call
+    /// to `next` on the iterator returns a tuple with the next match as an encrypted string (which
+    /// is the pattern itself, or empty if there are no more matches) and a boolean indicating
+    /// `Some` (true) or `None` (false).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    /// let (s, pat) = ("1a2b3c", "b");
+    ///
+    /// let enc_s = FheString::new(&ck, &s, None);
+    /// let enc_pat = GenericPattern::Enc(FheString::new(&ck, &pat, Some(1)));
+    ///
+    /// let mut matches_iter = sk.matches(&enc_s, &enc_pat);
+    /// let (first_item, first_is_some) = matches_iter.next(&sk);
+    /// let (_, no_more_items) = matches_iter.next(&sk);
+    ///
+    /// assert_eq!(ck.decrypt_ascii(&first_item), "b");
+    /// assert!(ck.key().decrypt_bool(&first_is_some));
+    /// assert!(!ck.key().decrypt_bool(&no_more_items));
+    /// ```
+    pub fn matches(&self, str: &FheString, pat: &GenericPattern) -> Matches {
+        let internal = self.match_indices_internal(str, pat, false);
+
+        Matches { internal }
+    }
+
+    fn match_indices_internal(
+        &self,
+        str: &FheString,
+        pat: &GenericPattern,
+        reverse: bool,
+    ) -> MatchIndicesInternal {
+        // There can be at most one match per char (empty pattern) or per byte
+        let max_counter = self.split_max_counter(str, pat);
+
+        MatchIndicesInternal {
+            reverse,
+            state: str.clone(),
+            pat: pat.clone(),
+            offset: self.key.create_trivial_zero_radix(self.index_blocks()),
+            counter: 0,
+            max_counter,
+            counter_lt_max: self.key.create_trivial_boolean_block(true),
+        }
+    }
+}
+
+impl FheIndexIterator for MatchIndicesInternal {
+    fn next(&mut self, sk: &ServerKey) -> (RadixCiphertext, BooleanBlock) {
+        let trivial_or_enc_pat = match &self.pat {
+            GenericPattern::Clear(pat) => FheString::trivial(sk, pat.str()),
+            GenericPattern::Enc(pat) => pat.clone(),
+        };
+
+        let (((index, mut is_some), empty_pat_step), pat_len) = rayon::join(
+            || {
+                rayon::join(
+                    || {
+                        if self.reverse {
+                            sk.rfind(&self.state, &self.pat)
+                        } else {
+                            sk.find(&self.state, &self.pat)
+                        }
+                    },
+                    // After an empty match we have to skip the next char, as otherwise we would
+                    // get the same match again
+                    || sk.empty_pat_char_width(&self.state, &trivial_or_enc_pat, self.reverse),
+                )
+            },
+            || match sk.byte_len(&trivial_or_enc_pat) {
+                FheStringLen::Padding(enc_val) => enc_val,
+                FheStringLen::NoPadding(val) => {
+                    sk.key.create_trivial_radix(val as u32, sk.index_blocks())
+                }
+            },
+        );
+
+        let state_len = self.state.chars().len() as u32;
+
+        let (result, mut state) = if self.reverse {
+            // The next matches can only be before the current one, so we keep the state up to the
+            // match index (minus the skipped char if the pattern is empty). An empty pattern also
+            // matches at index 0, where there's no char to skip, so we keep nothing in that case
+            let keep = match empty_pat_step {
+                Some(step) => {
+                    let (has_char_before, keep) = rayon::join(
+                        || sk.key.ge_parallelized(&index, &step),
+                        || sk.key.sub_parallelized(&index, &step),
+                    );
+                    let zero = sk.key.create_trivial_zero_radix(sk.index_blocks());
+
+                    sk.key
+                        .if_then_else_parallelized(&has_char_before, &keep, &zero)
+                }
+                None => index.clone(),
+            };
+
+            let shift = sk.key.create_trivial_radix(state_len, sk.index_blocks());
+            let shift = sk.key.sub_parallelized(&shift, &keep);
+            let shift = sk.key.scalar_min_parallelized(&shift, state_len);
+
+            let lhs = sk.right_shift_chars(&self.state, &shift);
+
+            (index, sk.left_shift_chars(&lhs, &shift))
+        } else {
+            // The next matches can only be after the current one (without overlapping), so we
+            // remove the chars up to the end of the match (plus the skipped char if the pattern is
+            // empty)
+            let (result, mut skip) = rayon::join(
+                || sk.key.add_parallelized(&self.offset, &index),
+                || sk.key.add_parallelized(&index, &pat_len),
+            );
+
+            if let Some(step) = empty_pat_step {
+                sk.key.add_assign_parallelized(&mut skip, &step);
+            }
+
+            let skip = sk.key.scalar_min_parallelized(&skip, state_len);
+            sk.key.add_assign_parallelized(&mut self.offset, &skip);
+
+            (result, sk.left_shift_chars(&self.state, &skip))
+        };
+
+        // The shifts may have introduced nulls at the end
+        if self.state.is_padded() {
+            state.set_is_padded(true);
+        } else {
+            state.append_null(sk);
+        }
+        self.state = state;
+
+        // If pattern is empty, `is_some` is always true, so we make it false when we have reached
+        // the last possible counter value
+        sk.key
+            .boolean_bitand_assign(&mut is_some, &self.counter_lt_max);
+
+        self.counter_lt_max = sk
+            .key
+            .scalar_gt_parallelized(&self.max_counter, self.counter);
+
+        self.counter += 1;
+
+        (result, is_some)
+    }
+}
+
+impl FheIndexIterator for MatchIndices {
+    fn next(&mut self, sk: &ServerKey) -> (RadixCiphertext, BooleanBlock) {
+        self.internal.next(sk)
+    }
+}
+
+impl FheIndexIterator for RMatchIndices {
+    fn next(&mut self, sk: &ServerKey) -> (RadixCiphertext, BooleanBlock) {
+        self.internal.next(sk)
+    }
+}
+
+impl FheStringIterator for Matches {
+    fn next(&mut self, sk: &ServerKey) -> (FheString, BooleanBlock) {
+        let (_, is_some) = self.internal.next(sk);
+
+        let pat = match &self.internal.pat {
+            GenericPattern::Clear(pat) => FheString::trivial(sk, pat.str()),
+            GenericPattern::Enc(pat) => pat.clone(),
+        };
+
+        // Every match is the pattern itself, which we only return when there's a match
+        let result = sk.conditional_string(&is_some, pat, &FheString::empty());
+
+        (result, is_some)
+    }
+}
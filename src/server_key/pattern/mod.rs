@@ -1,13 +1,24 @@
 mod contains;
+mod count;
 mod find;
+mod ignore_case;
+mod matches;
+mod multi;
+mod regex;
 mod replace;
 mod split;
 mod strip;
+mod wildcard;
 
-use crate::ciphertext::{FheAsciiChar, FheString};
+pub use matches::{MatchIndices, Matches, RMatchIndices};
+pub use split::{
+    RSplit, RSplitN, RSplitTerminator, Split, SplitInclusive, SplitN, SplitTerminator,
+};
+
+use crate::ciphertext::{FheAsciiChar, FheString, GenericPattern};
 use crate::server_key::{CharIter, FheStringIsEmpty, ServerKey};
 use std::ops::Range;
-use tfhe::integer::BooleanBlock;
+use tfhe::integer::{BooleanBlock, RadixCiphertext};
 
 // Useful for handling cases in which we know if there is or there isn't a match just by looking at
 // the lengths
@@ -17,6 +28,14 @@ enum IsMatch {
     None,
 }
 
+// Returns false if we know that the pattern isn't empty without any homomorphic operation
+fn can_be_empty(pat: &GenericPattern) -> bool {
+    match pat {
+        GenericPattern::Clear(pat) => pat.str().is_empty(),
+        GenericPattern::Enc(pat) => pat.is_padded() || pat.chars().is_empty(),
+    }
+}
+
 // `length_checks` allow us to return early in the pattern matching functions, while the other
 // methods below contain logic for the different cases
 impl ServerKey {
@@ -56,6 +75,35 @@ impl ServerKey {
         IsMatch::None
     }
 
+    // An empty pattern always matches at the very start of `str` (or at the end if `reverse`), so
+    // to get the next empty match we have to advance by the width of the first (or last) char,
+    // such that we stay at UTF-8 char boundaries. Returns that width if the pattern is empty (or an
+    // encrypted zero if the pattern is not), and `None` if we know the pattern isn't empty
+    fn empty_pat_char_width(
+        &self,
+        str: &FheString,
+        pat: &FheString,
+        reverse: bool,
+    ) -> Option<RadixCiphertext> {
+        let char_width = || {
+            if reverse {
+                self.last_char_width(str)
+            } else {
+                self.first_char_width(str)
+            }
+        };
+
+        match self.is_empty(pat) {
+            FheStringIsEmpty::Padding(enc) => Some(self.key.if_then_else_parallelized(
+                &enc,
+                &char_width(),
+                &self.key.create_trivial_zero_radix(self.index_blocks()),
+            )),
+            FheStringIsEmpty::NoPadding(true) => Some(char_width()),
+            FheStringIsEmpty::NoPadding(false) => None,
+        }
+    }
+
     fn ends_with_cases<'a>(
         &'a self,
         str: &'a FheString,
@@ -0,0 +1,409 @@
+use crate::ciphertext::{FheAsciiChar, FheString, GenericPattern};
+use crate::server_key::pattern::IsMatch;
+use crate::server_key::ServerKey;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+use tfhe::integer::{BooleanBlock, IntegerRadixCiphertext, RadixCiphertext};
+
+#[derive(Default)]
+struct TrieNode {
+    children: BTreeMap<u8, usize>,
+    // Indices (in the patterns slice) of the patterns that end at this node
+    pat_indices: Vec<usize>,
+}
+
+// Prefix tree of the clear patterns. The patterns that share a prefix share the homomorphic
+// comparisons for it, and every char of str is only compared once with each byte value
+struct Trie {
+    nodes: Vec<TrieNode>,
+    // The byte values found at each depth, i.e. the ones we compare each char of str with
+    depth_bytes: Vec<BTreeSet<u8>>,
+}
+
+impl Trie {
+    fn new<'a>(pats: impl Iterator<Item = (usize, &'a str)>) -> Self {
+        let mut nodes = vec![TrieNode::default()];
+        let mut depth_bytes: Vec<BTreeSet<u8>> = Vec::new();
+
+        for (pat_index, pat) in pats {
+            let mut node = 0;
+
+            for (depth, byte) in pat.bytes().enumerate() {
+                if depth_bytes.len() == depth {
+                    depth_bytes.push(BTreeSet::new());
+                }
+                depth_bytes[depth].insert(byte);
+
+                node = match nodes[node].children.get(&byte) {
+                    Some(&child) => child,
+                    None => {
+                        nodes.push(TrieNode::default());
+
+                        let child = nodes.len() - 1;
+                        nodes[node].children.insert(byte, child);
+                        child
+                    }
+                };
+            }
+
+            nodes[node].pat_indices.push(pat_index);
+        }
+
+        Trie { nodes, depth_bytes }
+    }
+}
+
+impl ServerKey {
+    // For each char of str, the result of comparing it with every byte that a clear pattern can
+    // have at that index
+    fn trie_char_eqs(&self, str: &FheString, trie: &Trie) -> Vec<BTreeMap<u8, BooleanBlock>> {
+        str.chars()
+            .par_iter()
+            .enumerate()
+            .map(|(i, char)| {
+                // A byte at depth `d` can only be compared with the chars at index `d` or greater
+                let bytes: BTreeSet<u8> = trie
+                    .depth_bytes
+                    .iter()
+                    .take(i + 1)
+                    .flatten()
+                    .copied()
+                    .collect();
+
+                bytes
+                    .into_par_iter()
+                    .map(|byte| {
+                        let is_eq = self.key.scalar_eq_parallelized(char.ciphertext(), byte);
+                        (byte, is_eq)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Returns whether each clear pattern in the trie matches str at the `start` index, as
+    // (pattern index, is_matched) pairs. Patterns that can't fit in str are omitted
+    fn trie_matches_at(
+        &self,
+        trie: &Trie,
+        char_eqs: &[BTreeMap<u8, BooleanBlock>],
+        start: usize,
+    ) -> Vec<(usize, BooleanBlock)> {
+        let mut result = Vec::new();
+
+        // The root node is the empty prefix, which matches without any comparison
+        let mut stack: Vec<(usize, usize, Option<BooleanBlock>)> = vec![(0, 0, None)];
+
+        while let Some((node, depth, is_matched)) = stack.pop() {
+            let node = &trie.nodes[node];
+
+            match &is_matched {
+                Some(is_matched) => {
+                    result.extend(node.pat_indices.iter().map(|&i| (i, is_matched.clone())));
+                }
+                // An empty pattern always matches at index 0, so we don't need the other indices
+                None if start == 0 => {
+                    let is_matched = self.key.create_trivial_boolean_block(true);
+                    result.extend(node.pat_indices.iter().map(|&i| (i, is_matched.clone())));
+                }
+                None => (),
+            }
+
+            let Some(eqs) = char_eqs.get(start + depth) else {
+                continue;
+            };
+
+            for (byte, &child) in &node.children {
+                let child_is_matched = match &is_matched {
+                    Some(is_matched) => self.key.boolean_bitand(is_matched, &eqs[byte]),
+                    None => eqs[byte].clone(),
+                };
+
+                stack.push((child, depth + 1, Some(child_is_matched)));
+            }
+        }
+
+        result
+    }
+
+    // Same as `find`, but returns whether the pattern matches at each possible index
+    fn enc_pattern_matches(&self, str: &FheString, pat: &FheString) -> Vec<(usize, BooleanBlock)> {
+        match self.length_checks(str, pat) {
+            IsMatch::Clear(true) => return vec![(0, self.key.create_trivial_boolean_block(true))],
+            IsMatch::Clear(false) => return Vec::new(),
+
+            // This variant is only returned in the empty string case so the only index is 0
+            IsMatch::Cipher(val) => return vec![(0, val)],
+            IsMatch::None => (),
+        }
+
+        let null = (!str.is_padded() && pat.is_padded()).then_some(FheAsciiChar::null(self));
+
+        let (str_iter, pat_iter, iter) = self.contains_cases(str, pat, null.as_ref());
+
+        let iter_values: Vec<_> = iter.collect();
+
+        self.shifted_matches(
+            (str_iter, pat_iter),
+            iter_values.into_par_iter(),
+            pat.is_padded(),
+        )
+    }
+
+    // Returns, for each index of str (plus the one after the last char), the (pattern index,
+    // is_matched) pairs of the patterns that can match there, sorted by pattern index
+    fn multi_pattern_matches(
+        &self,
+        str: &FheString,
+        pats: &[GenericPattern],
+    ) -> Vec<Vec<(usize, BooleanBlock)>> {
+        let str_len = str.chars().len();
+
+        let clear_pats = pats.iter().enumerate().filter_map(|(i, pat)| match pat {
+            GenericPattern::Clear(pat) => Some((i, pat.str())),
+            GenericPattern::Enc(_) => None,
+        });
+        let trie = Trie::new(clear_pats);
+
+        let (clear_matches, enc_matches) = rayon::join(
+            || {
+                let char_eqs = self.trie_char_eqs(str, &trie);
+
+                (0..str_len + 1)
+                    .into_par_iter()
+                    .flat_map_iter(|start| {
+                        self.trie_matches_at(&trie, &char_eqs, start)
+                            .into_iter()
+                            .map(move |(pat_index, is_matched)| (start, pat_index, is_matched))
+                    })
+                    .collect::<Vec<_>>()
+            },
+            // Encrypted patterns can't share comparisons, so we match them one by one
+            || {
+                pats.par_iter()
+                    .enumerate()
+                    .filter_map(|(i, pat)| match pat {
+                        GenericPattern::Enc(pat) => Some((i, pat)),
+                        GenericPattern::Clear(_) => None,
+                    })
+                    .flat_map_iter(|(pat_index, pat)| {
+                        self.enc_pattern_matches(str, pat)
+                            .into_iter()
+                            .map(move |(start, is_matched)| (start, pat_index, is_matched))
+                    })
+                    .collect::<Vec<_>>()
+            },
+        );
+
+        let mut matches_by_index: Vec<Vec<_>> = (0..str_len + 1).map(|_| Vec::new()).collect();
+
+        for (start, pat_index, is_matched) in clear_matches.into_iter().chain(enc_matches) {
+            matches_by_index[start].push((pat_index, is_matched));
+        }
+
+        for matches in matches_by_index.iter_mut() {
+            matches.sort_by_key(|(pat_index, _)| *pat_index);
+        }
+
+        matches_by_index
+    }
+
+    /// Returns `true` if any of the given patterns (each either encrypted or clear) matches a
+    /// substring of this encrypted string. This is equivalent to calling [`ServerKey::contains`]
+    /// with each pattern and combining the results, but much faster with many clear patterns.
+    ///
+    /// The clear patterns are arranged in a prefix tree, so every char of the string is only
+    /// compared once with each byte value found in the patterns, and the patterns that share a
+    /// prefix share the comparisons for it. Returns `false` if `pats` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    /// let s = "transfer the funds";
+    ///
+    /// let enc_s = FheString::new(&ck, &s, Some(2));
+    /// let keywords: Vec<_> = ["fund", "fraud", "transaction"]
+    ///     .iter()
+    ///     .map(|pat| GenericPattern::Clear(ClearString::new(pat.to_string())))
+    ///     .collect();
+    ///
+    /// let result = sk.contains_any(&enc_s, &keywords);
+    ///
+    /// assert!(ck.key().decrypt_bool(&result));
+    /// ```
+    pub fn contains_any(&self, str: &FheString, pats: &[GenericPattern]) -> BooleanBlock {
+        let block_vec: Vec<_> = self
+            .multi_pattern_matches(str, pats)
+            .into_iter()
+            .flatten()
+            .map(|(_, is_matched)| {
+                let radix: RadixCiphertext = is_matched.into_radix(1, &self.key);
+                radix.into_blocks()[0].clone()
+            })
+            .collect();
+
+        if block_vec.is_empty() {
+            return self.key.create_trivial_boolean_block(false);
+        }
+
+        // This will be 0 if there was no match, non-zero otherwise
+        let combined_radix = RadixCiphertext::from(block_vec);
+
+        self.key.scalar_ne_parallelized(&combined_radix, 0)
+    }
+
+    /// Returns a tuple containing the byte index of the first match of any of the given patterns
+    /// (each either encrypted or clear) in this encrypted string, the index in `pats` of the
+    /// pattern that matched there, and a boolean indicating if a match was found.
+    ///
+    /// If several patterns match at the first index, the one that comes first in `pats` is
+    /// returned. If no pattern matches, the boolean part is `false` and both indices are 0. See
+    /// [`ServerKey::contains_any`] for how the comparisons are shared between clear patterns.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    /// let s = "please send the password";
+    ///
+    /// let enc_s = FheString::new(&ck, &s, None);
+    /// let pats = [
+    ///     GenericPattern::Clear(ClearString::new("password".to_string())),
+    ///     GenericPattern::Enc(FheString::new(&ck, "send", Some(1))),
+    ///     GenericPattern::Clear(ClearString::new("pass".to_string())),
+    /// ];
+    ///
+    /// let (index, pat_index, found) = sk.find_any(&enc_s, &pats);
+    ///
+    /// assert!(ck.key().decrypt_bool(&found));
+    /// assert_eq!(ck.key().decrypt_radix::<u32>(&index), 7); // "send" starts at index 7
+    /// assert_eq!(ck.key().decrypt_radix::<u32>(&pat_index), 1);
+    /// ```
+    pub fn find_any(
+        &self,
+        str: &FheString,
+        pats: &[GenericPattern],
+    ) -> (RadixCiphertext, RadixCiphertext, BooleanBlock) {
+        let matches_by_index = self.multi_pattern_matches(str, pats);
+
+        // For each index, whether any pattern matches and the first one in `pats` that does
+        let first_matches: Vec<_> = matches_by_index
+            .into_par_iter()
+            .enumerate()
+            .filter(|(_, matches)| !matches.is_empty())
+            .map(|(i, matches)| {
+                let mut any_match = self.key.create_trivial_boolean_block(false);
+                let mut first_pat = self.key.create_trivial_zero_radix(self.index_blocks());
+
+                for (pat_index, is_matched) in matches.into_iter().rev() {
+                    let pat_index = self
+                        .key
+                        .create_trivial_radix(pat_index as u32, self.index_blocks());
+
+                    rayon::join(
+                        || {
+                            first_pat = self.key.if_then_else_parallelized(
+                                &is_matched,
+                                &pat_index,
+                                &first_pat,
+                            )
+                        },
+                        || self.key.boolean_bitor_assign(&mut any_match, &is_matched),
+                    );
+                }
+
+                (i, first_pat, any_match)
+            })
+            .collect();
+
+        let mut found = self.key.create_trivial_boolean_block(false);
+        let mut first_index = self.key.create_trivial_zero_radix(self.index_blocks());
+        let mut first_pat = self.key.create_trivial_zero_radix(self.index_blocks());
+
+        for (i, pat_index, any_match) in first_matches.into_iter().rev() {
+            let index = self.key.create_trivial_radix(i as u32, self.index_blocks());
+
+            rayon::join(
+                || {
+                    rayon::join(
+                        || {
+                            first_index =
+                                self.key
+                                    .if_then_else_parallelized(&any_match, &index, &first_index)
+                        },
+                        || {
+                            first_pat = self
+                                .key
+                                .if_then_else_parallelized(&any_match, &pat_index, &first_pat)
+                        },
+                    )
+                },
+                || self.key.boolean_bitor_assign(&mut found, &any_match),
+            );
+        }
+
+        (first_index, first_pat, found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ciphertext::{ClearString, FheString, GenericPattern};
+    use crate::server_key::gen_keys;
+
+    #[test]
+    fn test_contains_and_find_any() {
+        let (ck, sk) = gen_keys();
+
+        // (str, str padding, [(pat, encrypted pat padding)])
+        let test_cases: [(&str, u32, &[(&str, Option<u32>)]); 7] = [
+            ("", 0, &[]),
+            ("", 2, &[("a", None), ("", Some(1))]),
+            ("hello world", 0, &[]),
+            ("hello world", 1, &[("word", None), ("worlds", Some(0))]),
+            (
+                "hello world",
+                0,
+                &[("world", None), ("lo", Some(1)), ("low", None)],
+            ),
+            ("abcabc", 2, &[("ca", None), ("bc", None), ("b", Some(2))]),
+            (
+                "abcabc",
+                0,
+                &[("cab", None), ("abcabcd", Some(1)), ("", None)],
+            ),
+        ];
+
+        for (str, str_pad, pats) in test_cases {
+            let enc_str = FheString::new(&ck, str, Some(str_pad));
+
+            let enc_pats: Vec<_> = pats
+                .iter()
+                .map(|&(pat, pat_pad)| match pat_pad {
+                    Some(pad) => GenericPattern::Enc(FheString::new(&ck, pat, Some(pad))),
+                    None => GenericPattern::Clear(ClearString::new(pat.to_string())),
+                })
+                .collect();
+
+            // The first index, and the first pattern at that index
+            let expected = pats
+                .iter()
+                .enumerate()
+                .filter_map(|(pat_index, (pat, _))| str.find(pat).map(|i| (i, pat_index)))
+                .min();
+
+            let contains = sk.contains_any(&enc_str, &enc_pats);
+            assert_eq!(ck.key().decrypt_bool(&contains), expected.is_some());
+
+            let (index, pat_index, found) = sk.find_any(&enc_str, &enc_pats);
+            let dec_found = ck.key().decrypt_bool(&found);
+            let dec_index = ck.key().decrypt_radix::<u32>(&index) as usize;
+            let dec_pat_index = ck.key().decrypt_radix::<u32>(&pat_index) as usize;
+
+            assert_eq!(dec_found.then_some((dec_index, dec_pat_index)), expected);
+        }
+    }
+}
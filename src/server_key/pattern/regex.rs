@@ -0,0 +1,236 @@
+use crate::ciphertext::{FheAsciiChar, FheString};
+use crate::regex::{ClearRegex, RegexNode};
+use crate::server_key::{MaybeClear, ServerKey};
+use rayon::prelude::*;
+use tfhe::integer::BooleanBlock;
+
+impl ServerKey {
+    // Returns whether the char is in any of the (non null) byte ranges
+    fn char_in_class(&self, char: &FheAsciiChar, class: &[(u8, u8)]) -> BooleanBlock {
+        let in_ranges: Vec<_> = class
+            .par_iter()
+            .map(|&(lo, hi)| {
+                if lo == hi {
+                    return self.key.scalar_eq_parallelized(char.ciphertext(), lo);
+                }
+
+                let (ge_lo, le_hi) = rayon::join(
+                    || self.key.scalar_ge_parallelized(char.ciphertext(), lo),
+                    || self.key.scalar_le_parallelized(char.ciphertext(), hi),
+                );
+
+                self.key.boolean_bitand(&ge_lo, &le_hi)
+            })
+            .collect();
+
+        in_ranges
+            .into_iter()
+            .reduce(|a, b| self.key.boolean_bitor(&a, &b))
+            .unwrap_or_else(|| self.key.create_trivial_boolean_block(false))
+    }
+
+    /// Returns `true` if the given clear regex matches this encrypted string, anywhere unless it's
+    /// anchored with `^` or `$`. This is the equivalent of `Regex::is_match` from the `regex`
+    /// crate. See [`ClearRegex`] for the supported syntax.
+    ///
+    /// The regex is compiled to an automaton, whose states are updated homomorphically with each
+    /// char of the string. As in [`ServerKey::contains`], padding nulls are ignored and `$` matches
+    /// at the end of the actual string.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    /// let email = ClearRegex::new(r"^[\w.+-]+@[a-z\d-]+(\.[a-z]+)+$".to_string());
+    ///
+    /// let enc_s = FheString::new(&ck, "alice@example.com", Some(3));
+    /// let result = sk.regex_is_match(&enc_s, &email);
+    ///
+    /// assert!(ck.key().decrypt_bool(&result));
+    ///
+    /// let enc_s = FheString::new(&ck, "alice@example", None);
+    /// let result = sk.regex_is_match(&enc_s, &email);
+    ///
+    /// assert!(!ck.key().decrypt_bool(&result));
+    /// ```
+    pub fn regex_is_match(&self, str: &FheString, regex: &ClearRegex) -> BooleanBlock {
+        let chars = str.chars();
+        let str_len = chars.len();
+        let nodes = regex.nodes();
+
+        // For each char, whether it's in each class, and for each index whether it's the end of str
+        let (class_matches, is_end) = rayon::join(
+            || {
+                chars
+                    .par_iter()
+                    .map(|char| {
+                        regex
+                            .classes()
+                            .par_iter()
+                            .map(|class| self.char_in_class(char, class))
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>()
+            },
+            || {
+                (0..str_len + 1)
+                    .into_par_iter()
+                    .map(|i| match chars.get(i) {
+                        Some(char) if str.is_padded() => {
+                            let is_null = self.key.scalar_eq_parallelized(char.ciphertext(), 0u8);
+                            MaybeClear::Cipher(is_null)
+                        }
+                        Some(_) => MaybeClear::Clear(false),
+                        None => MaybeClear::Clear(true),
+                    })
+                    .collect::<Vec<_>>()
+            },
+        );
+
+        // We compute once the closures of the nodes that can be reached, i.e. the start node and
+        // the ones after a class. The `^` anchor only holds for the start node at the first index
+        let start_closure = regex.closure(regex.start(), true);
+        let mut is_reachable = vec![false; nodes.len()];
+        is_reachable[regex.start()] = true;
+        for node in nodes {
+            if let RegexNode::Class { next, .. } = node {
+                is_reachable[*next] = true;
+            }
+        }
+
+        let closures: Vec<_> = is_reachable
+            .par_iter()
+            .enumerate()
+            .map(|(node, is_reachable)| is_reachable.then(|| regex.closure(node, false)))
+            .collect();
+
+        let mut result = MaybeClear::Clear(false);
+
+        // The nodes that we have reached after consuming the chars before `i`
+        let mut reached = vec![MaybeClear::Clear(false); nodes.len()];
+
+        for i in 0..str_len + 1 {
+            // A match can start at any index
+            reached[regex.start()] = MaybeClear::Clear(true);
+
+            let mut active = vec![MaybeClear::Clear(false); nodes.len()];
+
+            for (node, is_reached) in reached.iter().enumerate() {
+                if let MaybeClear::Clear(false) = is_reached {
+                    continue;
+                }
+
+                let closure = if i == 0 {
+                    &start_closure
+                } else {
+                    closures[node]
+                        .as_ref()
+                        .expect("Only reachable nodes are reached")
+                };
+
+                for &(target, needs_end) in closure {
+                    let is_active = if needs_end {
+                        self.maybe_clear_and(is_reached, &is_end[i])
+                    } else {
+                        is_reached.clone()
+                    };
+
+                    active[target] = self.maybe_clear_or(&active[target], &is_active);
+                }
+            }
+
+            // The `Match` node is the first one
+            result = self.maybe_clear_or(&result, &active[0]);
+
+            if i == str_len || matches!(result, MaybeClear::Clear(true)) {
+                break;
+            }
+
+            // Nulls are not in any class, so once we reach the padding every node is inactive
+            let consumed: Vec<_> = active
+                .par_iter()
+                .enumerate()
+                .filter_map(|(node, is_active)| match (&nodes[node], is_active) {
+                    (_, MaybeClear::Clear(false)) => None,
+                    (RegexNode::Class { class, next }, is_active) => {
+                        let in_class = MaybeClear::Cipher(class_matches[i][*class].clone());
+                        Some((*next, self.maybe_clear_and(is_active, &in_class)))
+                    }
+                    _ => None,
+                })
+                .collect();
+
+            reached = vec![MaybeClear::Clear(false); nodes.len()];
+            for (next, is_reached) in consumed {
+                reached[next] = self.maybe_clear_or(&reached[next], &is_reached);
+            }
+        }
+
+        result.into_boolean_block(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ciphertext::FheString;
+    use crate::regex::ClearRegex;
+    use crate::server_key::gen_keys;
+
+    #[test]
+    fn test_regex_is_match() {
+        let (ck, sk) = gen_keys();
+
+        // (regex, [(str, padding, expected)])
+        let test_cases = [
+            ("", vec![("", 0, true), ("abc", 1, true)]),
+            ("^$", vec![("", 0, true), ("", 2, true), ("a", 1, false)]),
+            (
+                "b+c?$",
+                vec![("abb", 2, true), ("abbc", 0, true), ("abca", 0, false)],
+            ),
+            (
+                "^a.c",
+                vec![
+                    ("abc", 1, true),
+                    ("añc", 0, true),
+                    ("ac", 2, false),
+                    ("a\nc", 0, false),
+                ],
+            ),
+            (
+                r"^\d{3}-\d{2,4}$",
+                vec![
+                    ("555-1234", 1, true),
+                    ("555-1", 0, false),
+                    ("55-123", 0, false),
+                ],
+            ),
+            (
+                "(cat|dog)s?$",
+                vec![("hotdogs", 0, true), ("cats!", 2, false)],
+            ),
+            (
+                r"^[^\s,]+,",
+                vec![("a,b", 0, true), ("é,", 1, true), (" a,", 0, false)],
+            ),
+            ("a$b", vec![("ab", 0, false), ("a", 1, false)]),
+            (
+                "^[]a]+$",
+                vec![("]a]", 0, true), ("a", 1, true), ("a]b", 0, false)],
+            ),
+        ];
+
+        for (regex, cases) in test_cases {
+            let clear_regex = ClearRegex::new(regex.to_string());
+
+            for (str, pad, expected) in cases {
+                let enc_str = FheString::new_utf8(&ck, str, Some(pad));
+
+                let result = sk.regex_is_match(&enc_str, &clear_regex);
+
+                assert_eq!(ck.key().decrypt_bool(&result), expected, "{regex} {str}");
+            }
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::ciphertext::{FheString, GenericPattern, UIntArg};
-use crate::server_key::pattern::IsMatch;
+use crate::server_key::pattern::{can_be_empty, IsMatch};
 use crate::server_key::{FheStringIsEmpty, FheStringLen, ServerKey};
 use tfhe::integer::{BooleanBlock, RadixCiphertext};
 
@@ -29,7 +29,9 @@ impl ServerKey {
 
         let (mut replaced, rhs) = rayon::join(
             || {
-                let str_len = self.key.create_trivial_radix(str.chars().len() as u32, 16);
+                let str_len = self
+                    .key
+                    .create_trivial_radix(str.chars().len() as u32, self.index_blocks());
 
                 // Get the [lhs] shifting right by [from, rhs].len()
                 let shift_right = self.key.sub_parallelized(&str_len, find_index);
@@ -73,7 +75,7 @@ impl ServerKey {
                 let add_to_index = self.key.if_then_else_parallelized(
                     replace,
                     enc_to_len,
-                    &self.key.create_trivial_zero_radix(16),
+                    &self.key.create_trivial_zero_radix(self.index_blocks()),
                 );
                 self.key.add_parallelized(find_index, &add_to_index)
             },
@@ -87,8 +89,16 @@ impl ServerKey {
         from: &GenericPattern,
         to: &FheString,
         enc_n: Option<&RadixCiphertext>,
+        ignore_case: bool,
     ) {
-        let mut skip = self.key.create_trivial_zero_radix(16);
+        let mut skip = self.key.create_trivial_zero_radix(self.index_blocks());
+
+        // When ignoring case we search in a lowercased copy of the original str (`from` is already
+        // lowercased), which is never replaced. The part after `search_skip` in this copy is the
+        // same as the part after `skip` in the result, so a match at `search_skip + i` is replaced
+        // at `skip + i`
+        let lowercase = ignore_case.then(|| self.to_lowercase(result));
+        let mut search_skip = skip.clone();
         let trivial_or_enc_from = match from {
             GenericPattern::Clear(from) => FheString::trivial(self, from.str()),
             GenericPattern::Enc(from) => from.clone(),
@@ -98,18 +108,26 @@ impl ServerKey {
             || {
                 rayon::join(
                     || self.is_empty(&trivial_or_enc_from),
-                    || self.len(&trivial_or_enc_from),
+                    || self.byte_len(&trivial_or_enc_from),
                 )
             },
             || {
                 rayon::join(
-                    || self.len(result),
-                    || match self.len(to) {
-                        FheStringLen::Padding(enc_val) => enc_val,
-                        FheStringLen::NoPadding(val) => {
-                            self.key.create_trivial_radix(val as u32, 16)
+                    // With an empty `from` there's a match between every char, so in that case we
+                    // need the number of UTF-8 chars rather than bytes
+                    || {
+                        if can_be_empty(from) {
+                            FheStringLen::Padding(self.chars_count(result))
+                        } else {
+                            self.byte_len(result)
                         }
                     },
+                    || match self.byte_len(to) {
+                        FheStringLen::Padding(enc_val) => enc_val,
+                        FheStringLen::NoPadding(val) => self
+                            .key
+                            .create_trivial_radix(val as u32, self.index_blocks()),
+                    },
                 )
             },
         );
@@ -117,19 +135,55 @@ impl ServerKey {
         for i in 0..iterations {
             let prev = result.clone();
 
-            let (_, no_more_matches) = rayon::join(
+            let (char_width, no_more_matches) = rayon::join(
                 || {
+                    let (searched, searched_skip) = match &lowercase {
+                        Some(lower) => (lower, &search_skip),
+                        None => (&*result, &skip),
+                    };
+
                     // We first shift str `skip` chars left to ignore them and check if there's a
                     // match
-                    let shifted_str = self.left_shift_chars(result, &skip);
+                    let shifted_str = self.left_shift_chars(searched, searched_skip);
+
+                    // If `from` is empty the match is at the start of the shifted str, so the
+                    // char that follows the replacement is the first char of the shifted str
+                    let char_width = match &from_is_empty {
+                        FheStringIsEmpty::NoPadding(false) => None,
+                        _ => Some(self.first_char_width(&shifted_str)),
+                    };
 
-                    let (mut index, is_match) = self.find(&shifted_str, from);
+                    let (shifted_index, is_match) = self.find(&shifted_str, from);
 
                     // We add `skip` to get the actual index of the pattern (in the non shifted str)
-                    self.key.add_assign_parallelized(&mut index, &skip);
+                    let (index, next_search_skip) = rayon::join(
+                        || self.key.add_parallelized(&shifted_index, &skip),
+                        || {
+                            lowercase.is_some().then(|| {
+                                // The lowercased copy is not replaced, so we skip the match itself
+                                let index = self.key.add_parallelized(&shifted_index, &search_skip);
+                                let end = match &from_len {
+                                    FheStringLen::NoPadding(len) => {
+                                        self.key.scalar_add_parallelized(&index, *len as u32)
+                                    }
+                                    FheStringLen::Padding(enc_len) => {
+                                        self.key.add_parallelized(&index, enc_len)
+                                    }
+                                };
+
+                                self.key.if_then_else_parallelized(&is_match, &end, &index)
+                            })
+                        },
+                    );
 
                     (*result, skip) =
                         self.replace_once(&is_match, &index, &from_len, &enc_to_len, result, to);
+
+                    if let Some(next_search_skip) = next_search_skip {
+                        search_skip = next_search_skip;
+                    }
+
+                    char_width
                 },
                 || self.no_more_matches(&str_len, &from_is_empty, i, enc_n),
             );
@@ -137,15 +191,24 @@ impl ServerKey {
             rayon::join(
                 || *result = self.conditional_string(&no_more_matches, prev, result),
                 // If we replace "" to "a" in the "ww" str, we get "awawa". So when `from_is_empty`
-                // we need to move to the next space between letters by adding 1 to the skip value
-                || match &from_is_empty {
-                    FheStringIsEmpty::Padding(enc) => self
-                        .key
-                        .add_assign_parallelized(&mut skip, &enc.clone().into_radix(1, &self.key)),
+                // we need to move to the next space between chars by adding the char width (1 for
+                // ASCII chars) to the skip value
+                || {
+                    let step = match (&from_is_empty, char_width) {
+                        (FheStringIsEmpty::Padding(enc), Some(width)) => {
+                            self.key.if_then_else_parallelized(
+                                enc,
+                                &width,
+                                &self.key.create_trivial_zero_radix(self.index_blocks()),
+                            )
+                        }
+                        (FheStringIsEmpty::NoPadding(true), Some(width)) => width,
+                        _ => return,
+                    };
 
-                    FheStringIsEmpty::NoPadding(clear) => {
-                        self.key
-                            .scalar_add_assign_parallelized(&mut skip, *clear as u8);
+                    self.key.add_assign_parallelized(&mut skip, &step);
+                    if lowercase.is_some() {
+                        self.key.add_assign_parallelized(&mut search_skip, &step);
                     }
                 },
             );
@@ -229,7 +292,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (s, from, to) = ("hello", "l", "r");
     ///
@@ -325,14 +389,14 @@ impl ServerKey {
                 // If n > max number of matches we use that max to avoid unnecessary iterations
                 let iterations = if *n > max { max } else { *n };
 
-                self.replace_n_times(iterations, &mut result, from, to, None);
+                self.replace_n_times(iterations, &mut result, from, to, None, false);
             }
 
             UIntArg::Enc(enc_n) => {
                 // As we don't know the number n we perform the maximum number of iterations
                 let max = enc_n.max().unwrap_or(u16::MAX);
 
-                self.replace_n_times(max, &mut result, from, to, Some(enc_n.cipher()));
+                self.replace_n_times(max, &mut result, from, to, Some(enc_n.cipher()), false);
             }
         }
 
@@ -351,7 +415,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (s, from, to) = ("hi", "i", "o");
     ///
@@ -371,6 +436,17 @@ impl ServerKey {
     /// assert_eq!(not_replaced, "hi"); // No match, original string returned
     /// ```
     pub fn replace(&self, str: &FheString, from: &GenericPattern, to: &FheString) -> FheString {
+        self.replace_all(str, from, to, false)
+    }
+
+    // When `ignore_case` is set, `from` must be already lowercased
+    pub(super) fn replace_all(
+        &self,
+        str: &FheString,
+        from: &GenericPattern,
+        to: &FheString,
+        ignore_case: bool,
+    ) -> FheString {
         let mut result = str.clone();
         let trivial_or_enc_from = match from {
             GenericPattern::Clear(from) => FheString::trivial(self, from.str()),
@@ -393,7 +469,7 @@ impl ServerKey {
 
         let max = self.max_matches(str, &trivial_or_enc_from);
 
-        self.replace_n_times(max, &mut result, from, to, None);
+        self.replace_n_times(max, &mut result, from, to, None, ignore_case);
 
         result
     }
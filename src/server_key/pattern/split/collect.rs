@@ -0,0 +1,440 @@
+use crate::ciphertext::{FheString, GenericPattern, UIntArg};
+use crate::server_key::pattern::split::{
+    SplitInternal, SplitNInternal, SplitNoLeading, SplitNoTrailing, SplitType,
+};
+use crate::server_key::pattern::IsMatch;
+use crate::server_key::{collect_sequential, FheStringIsEmpty, FheStringLen, ServerKey};
+use rayon::prelude::*;
+use tfhe::integer::{BooleanBlock, RadixCiphertext};
+
+impl ServerKey {
+    // The indices at which str is split by the pattern, in ascending order, along with whether it's
+    // split at each one. These are the non-overlapping matches of the pattern (found from the end
+    // if `reverse`) or, if the pattern is empty, the char boundaries of str
+    fn split_indices(
+        &self,
+        str: &FheString,
+        pat: &GenericPattern,
+        reverse: bool,
+    ) -> Vec<(usize, BooleanBlock)> {
+        let trivial_or_enc_pat = match pat {
+            GenericPattern::Clear(pat) => FheString::trivial(self, pat.str()),
+            GenericPattern::Enc(pat) => pat.clone(),
+        };
+
+        let boundaries = || {
+            self.char_boundaries(str)
+                .into_iter()
+                .enumerate()
+                .collect::<Vec<_>>()
+        };
+
+        let matches = || match self.length_checks(str, &trivial_or_enc_pat) {
+            IsMatch::None => self.match_flags(str, pat, &trivial_or_enc_pat, false, reverse),
+            // In the other cases there can only be a match if the pattern is empty
+            _ => vec![],
+        };
+
+        match self.is_empty(&trivial_or_enc_pat) {
+            FheStringIsEmpty::NoPadding(true) => boundaries(),
+            FheStringIsEmpty::NoPadding(false) => matches(),
+            FheStringIsEmpty::Padding(is_empty) => {
+                let (boundaries, matches) = rayon::join(boundaries, matches);
+                let is_not_empty = self.key.boolean_bitnot(&is_empty);
+
+                let mut match_at = vec![None; boundaries.len()];
+                for (i, is_matched) in matches {
+                    match_at[i] = Some(is_matched);
+                }
+
+                boundaries
+                    .into_par_iter()
+                    .zip(match_at)
+                    .map(|((i, is_boundary), is_matched)| {
+                        let mut is_split = self.key.boolean_bitand(&is_boundary, &is_empty);
+
+                        if let Some(is_matched) = is_matched {
+                            let is_split_match =
+                                self.key.boolean_bitand(&is_matched, &is_not_empty);
+
+                            self.key
+                                .boolean_bitor_assign(&mut is_split, &is_split_match);
+                        }
+
+                        (i, is_split)
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    fn len_index(&self, str: &FheString) -> RadixCiphertext {
+        match self.byte_len(str) {
+            FheStringLen::Padding(enc_val) => enc_val,
+            FheStringLen::NoPadding(val) => self
+                .key
+                .create_trivial_radix(val as u32, self.index_blocks()),
+        }
+    }
+
+    // Computes the first `max_items` items of a split iterator over str at once. Each item is the
+    // substring between two consecutive split indices (in the order of the iteration), so we find
+    // the nth split index for all the items in parallel, instead of splitting the remaining string
+    // one item after the other.
+    //
+    // `n` limits the number of items as in `SplitN`, where the last item is the rest of the string,
+    // and `no_trailing` removes the piece at the end of str if it's empty, as in `SplitTerminator`
+    // (or `RSplitTerminator`, where that piece is the first item)
+    fn split_collect(
+        &self,
+        str: &FheString,
+        pat: &GenericPattern,
+        split_type: &SplitType,
+        n: Option<&UIntArg>,
+        no_trailing: bool,
+        max_items: usize,
+    ) -> (Vec<(FheString, BooleanBlock)>, RadixCiphertext) {
+        let reverse = matches!(split_type, SplitType::RSplit);
+        let inclusive = matches!(split_type, SplitType::SplitInclusive);
+
+        // In reverse the piece at the end of str is the first item, so if it's removed we need one
+        // more item
+        let num_items = max_items + usize::from(reverse && no_trailing);
+
+        let ((splits, pat_len), str_len) = rayon::join(
+            || {
+                rayon::join(
+                    || {
+                        let mut indices = self.split_indices(str, pat, reverse);
+                        if reverse {
+                            indices.reverse();
+                        }
+
+                        self.first_set_positions(&indices, num_items)
+                    },
+                    || {
+                        let trivial_or_enc_pat = match pat {
+                            GenericPattern::Clear(pat) => FheString::trivial(self, pat.str()),
+                            GenericPattern::Enc(pat) => pat.clone(),
+                        };
+
+                        self.len_index(&trivial_or_enc_pat)
+                    },
+                )
+            },
+            || self.len_index(str),
+        );
+
+        let zero = self.key.create_trivial_zero_radix(self.index_blocks());
+
+        let items: Vec<_> = (0..num_items)
+            .into_par_iter()
+            .map(|k| {
+                if let Some(UIntArg::Clear(n)) = n {
+                    if k >= *n as usize {
+                        return (
+                            FheString::empty(),
+                            self.key.create_trivial_boolean_block(false),
+                        );
+                    }
+                }
+
+                // The item is between the previous split and the current one (if any)
+                let (split, is_split) = &splits[k];
+                let prev = k.checked_sub(1).map(|prev| &splits[prev]);
+                let split_end = || self.key.add_parallelized(split, &pat_len);
+
+                let (mut start, mut end) = if reverse {
+                    let start = self
+                        .key
+                        .if_then_else_parallelized(is_split, &split_end(), &zero);
+                    let end = prev.map_or_else(|| str_len.clone(), |(prev, _)| prev.clone());
+
+                    (start, end)
+                } else {
+                    let start = prev.map_or_else(
+                        || zero.clone(),
+                        |(prev, _)| self.key.add_parallelized(prev, &pat_len),
+                    );
+                    let split_end = if inclusive {
+                        split_end()
+                    } else {
+                        split.clone()
+                    };
+                    let end = self
+                        .key
+                        .if_then_else_parallelized(is_split, &split_end, &str_len);
+
+                    (start, end)
+                };
+
+                // There's an item if there was a split before it (or it's the first one)
+                let mut is_some = prev.map_or_else(
+                    || self.key.create_trivial_boolean_block(true),
+                    |(_, prev_is_split)| prev_is_split.clone(),
+                );
+
+                if let Some(UIntArg::Enc(n)) = n {
+                    // The item `n - 1` is the rest of the string
+                    let (is_last, within_n) = rayon::join(
+                        || self.key.scalar_eq_parallelized(n.cipher(), k as u64 + 1),
+                        || self.key.scalar_gt_parallelized(n.cipher(), k as u64),
+                    );
+
+                    if reverse {
+                        start = self.key.if_then_else_parallelized(&is_last, &zero, &start);
+                    } else {
+                        end = self.key.if_then_else_parallelized(&is_last, &str_len, &end);
+                    }
+
+                    self.key.boolean_bitand_assign(&mut is_some, &within_n);
+                } else if let Some(UIntArg::Clear(n)) = n {
+                    if k + 1 == *n as usize {
+                        if reverse {
+                            start = zero.clone();
+                        } else {
+                            end = str_len.clone();
+                        }
+                    }
+                }
+
+                // The piece at the end of str is the only one without a split after it
+                if no_trailing && !reverse {
+                    let is_not_empty = self.key.ne_parallelized(&start, &end);
+                    let is_kept = self.key.boolean_bitor(is_split, &is_not_empty);
+
+                    self.key.boolean_bitand_assign(&mut is_some, &is_kept);
+                }
+
+                (self.substring_between(str, &start, &end), is_some)
+            })
+            .collect();
+
+        let items = if reverse && no_trailing {
+            let leading_empty = match self.is_empty(&items[0].0) {
+                FheStringIsEmpty::Padding(enc) => enc,
+                FheStringIsEmpty::NoPadding(clear) => self.key.create_trivial_boolean_block(clear),
+            };
+            let not_leading_empty = self.key.boolean_bitnot(&leading_empty);
+
+            // If the first item is empty we skip it, i.e. we take the next item
+            items
+                .par_windows(2)
+                .map(|pair| {
+                    let [(item, is_some), (next_item, next_is_some)] = pair else {
+                        unreachable!("The windows have two items")
+                    };
+
+                    rayon::join(
+                        || self.conditional_string(&leading_empty, next_item.clone(), item),
+                        || {
+                            let (lhs, rhs) = rayon::join(
+                                || self.key.boolean_bitand(&leading_empty, next_is_some),
+                                || self.key.boolean_bitand(&not_leading_empty, is_some),
+                            );
+
+                            self.key.boolean_bitor(&lhs, &rhs)
+                        },
+                    )
+                })
+                .collect()
+        } else {
+            items
+        };
+
+        let count = self.count_some(&items);
+
+        (items, count)
+    }
+}
+
+impl SplitInternal {
+    pub(super) fn collect_items(
+        self,
+        sk: &ServerKey,
+        max_items: usize,
+    ) -> (Vec<(FheString, BooleanBlock)>, RadixCiphertext) {
+        // Once `next` is called the state is only the remaining string
+        if self.counter > 0 {
+            return collect_sequential(self, sk, max_items);
+        }
+
+        sk.split_collect(
+            &self.state,
+            &self.pat,
+            &self.split_type,
+            None,
+            false,
+            max_items,
+        )
+    }
+}
+
+impl SplitNInternal {
+    pub(super) fn collect_items(
+        self,
+        sk: &ServerKey,
+        max_items: usize,
+    ) -> (Vec<(FheString, BooleanBlock)>, RadixCiphertext) {
+        if self.counter > 0 {
+            return collect_sequential(self, sk, max_items);
+        }
+
+        let internal = &self.internal;
+        let n = Some(&self.n);
+
+        sk.split_collect(
+            &internal.state,
+            &internal.pat,
+            &internal.split_type,
+            n,
+            false,
+            max_items,
+        )
+    }
+}
+
+impl SplitNoTrailing {
+    pub(super) fn collect_items(
+        self,
+        sk: &ServerKey,
+        max_items: usize,
+    ) -> (Vec<(FheString, BooleanBlock)>, RadixCiphertext) {
+        if self.internal.counter > 0 {
+            return collect_sequential(self, sk, max_items);
+        }
+
+        let internal = &self.internal;
+        let (str, pat) = (&internal.state, &internal.pat);
+
+        sk.split_collect(str, pat, &internal.split_type, None, true, max_items)
+    }
+}
+
+impl SplitNoLeading {
+    pub(super) fn collect_items(
+        self,
+        sk: &ServerKey,
+        max_items: usize,
+    ) -> (Vec<(FheString, BooleanBlock)>, RadixCiphertext) {
+        // The first item is computed in advance in the first `next` call
+        if self.prev_return.is_some() {
+            return collect_sequential(self, sk, max_items);
+        }
+
+        let internal = &self.internal;
+        let (str, pat) = (&internal.state, &internal.pat);
+
+        sk.split_collect(str, pat, &internal.split_type, None, true, max_items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ciphertext::{ClearString, FheString, GenericPattern, UIntArg};
+    use crate::client_key::ClientKey;
+    use crate::server_key::{gen_keys, FheStringIterator};
+    use tfhe::integer::{BooleanBlock, RadixCiphertext};
+
+    const MAX_ITEMS: usize = 4;
+
+    fn assert_items(
+        ck: &ClientKey,
+        (items, count): (Vec<(FheString, BooleanBlock)>, RadixCiphertext),
+        expected: Vec<&str>,
+    ) {
+        let expected: Vec<_> = expected.into_iter().take(MAX_ITEMS).collect();
+        assert_eq!(items.len(), MAX_ITEMS);
+
+        let result: Vec<_> = items
+            .iter()
+            .filter(|(_, is_some)| ck.key().decrypt_bool(is_some))
+            .map(|(item, _)| ck.decrypt_utf8(item))
+            .collect();
+
+        assert_eq!(result, expected);
+        assert_eq!(ck.key().decrypt_radix::<u32>(&count), expected.len() as u32);
+    }
+
+    #[test]
+    fn test_collect_bounded() {
+        let (ck, sk) = gen_keys();
+
+        // (str, str padding, pat, pat padding)
+        let test_cases = [
+            ("", 1, "x", 0),
+            ("a,,b,", 0, ",", 1),
+            ("aaa", 2, "aa", 0),
+            ("héllo", 1, "", 2),
+        ];
+
+        for (str, str_pad, pat, pat_pad) in test_cases {
+            let enc_str = FheString::new_utf8(&ck, str, Some(str_pad));
+
+            for enc_pat in [
+                GenericPattern::Clear(ClearString::new(pat.to_string())),
+                GenericPattern::Enc(FheString::new(&ck, pat, Some(pat_pad))),
+            ] {
+                let split = sk.split(&enc_str, &enc_pat).collect_bounded(&sk, MAX_ITEMS);
+                assert_items(&ck, split, str.split(pat).collect());
+
+                let rsplit = sk
+                    .rsplit(&enc_str, &enc_pat)
+                    .collect_bounded(&sk, MAX_ITEMS);
+                assert_items(&ck, rsplit, str.rsplit(pat).collect());
+
+                let result = sk
+                    .split_terminator(&enc_str, &enc_pat)
+                    .collect_bounded(&sk, MAX_ITEMS);
+                assert_items(&ck, result, str.split_terminator(pat).collect());
+
+                let result = sk
+                    .rsplit_terminator(&enc_str, &enc_pat)
+                    .collect_bounded(&sk, MAX_ITEMS);
+                assert_items(&ck, result, str.rsplit_terminator(pat).collect());
+
+                let result = sk
+                    .split_inclusive(&enc_str, &enc_pat)
+                    .collect_bounded(&sk, MAX_ITEMS);
+                assert_items(&ck, result, str.split_inclusive(pat).collect());
+
+                for (n, is_enc) in [(0, false), (2, false), (0, true), (2, true)] {
+                    let n_arg = || {
+                        if is_enc {
+                            UIntArg::Enc(ck.encrypt_u16(n, None))
+                        } else {
+                            UIntArg::Clear(n)
+                        }
+                    };
+
+                    let result = sk
+                        .splitn(&enc_str, &enc_pat, n_arg())
+                        .collect_bounded(&sk, MAX_ITEMS);
+                    assert_items(&ck, result, str.splitn(n as usize, pat).collect());
+
+                    let result = sk
+                        .rsplitn(&enc_str, &enc_pat, n_arg())
+                        .collect_bounded(&sk, MAX_ITEMS);
+                    assert_items(&ck, result, str.rsplitn(n as usize, pat).collect());
+                }
+            }
+        }
+
+        // After calling `next` the remaining items are computed sequentially
+        let enc_str = FheString::new(&ck, "a,b,c", None);
+        let enc_pat = GenericPattern::Clear(ClearString::new(",".to_string()));
+
+        let mut split = sk.split(&enc_str, &enc_pat);
+        split.next(&sk);
+        assert_items(&ck, split.collect_bounded(&sk, MAX_ITEMS), vec!["b", "c"]);
+
+        for (str, str_pad) in [("", 0), (" a \t b  c", 1), ("abc", 0)] {
+            let enc_str = FheString::new(&ck, str, Some(str_pad));
+
+            let result = sk
+                .split_ascii_whitespace(&enc_str)
+                .collect_bounded(&sk, MAX_ITEMS);
+            assert_items(&ck, result, str.split_ascii_whitespace().collect());
+        }
+    }
+}
@@ -1,7 +1,12 @@
+mod collect;
 mod split_iters;
 
-use crate::ciphertext::{FheString, GenericPattern, UIntArg};
-use crate::server_key::pattern::IsMatch;
+pub use split_iters::{
+    RSplit, RSplitN, RSplitTerminator, Split, SplitInclusive, SplitN, SplitTerminator,
+};
+
+use crate::ciphertext::{ClearString, FheString, GenericPattern, UIntArg};
+use crate::server_key::pattern::{can_be_empty, IsMatch};
 use crate::server_key::{FheStringIsEmpty, FheStringIterator, FheStringLen, ServerKey};
 use tfhe::integer::{BooleanBlock, RadixCiphertext};
 
@@ -13,7 +18,9 @@ impl ServerKey {
         index: &RadixCiphertext,
         inclusive: bool,
     ) -> (FheString, FheString) {
-        let str_len = self.key.create_trivial_radix(str.chars().len() as u32, 16);
+        let str_len = self
+            .key
+            .create_trivial_radix(str.chars().len() as u32, self.index_blocks());
         let trivial_or_enc_pat = match pat {
             GenericPattern::Clear(pat) => FheString::trivial(self, pat.str()),
             GenericPattern::Enc(pat) => pat.clone(),
@@ -21,9 +28,11 @@ impl ServerKey {
 
         let (mut shift_right, real_pat_len) = rayon::join(
             || self.key.sub_parallelized(&str_len, index),
-            || match self.len(&trivial_or_enc_pat) {
+            || match self.byte_len(&trivial_or_enc_pat) {
                 FheStringLen::Padding(enc_val) => enc_val,
-                FheStringLen::NoPadding(val) => self.key.create_trivial_radix(val as u32, 16),
+                FheStringLen::NoPadding(val) => self
+                    .key
+                    .create_trivial_radix(val as u32, self.index_blocks()),
             },
         );
 
@@ -62,6 +71,72 @@ impl ServerKey {
         (lhs, rhs)
     }
 
+    /// Splits the encrypted string into two substrings at the byte index `mid`, which can be
+    /// either clear or encrypted, and returns a tuple of the two substrings along with a boolean
+    /// indicating if `mid` is within the length of the string (ignoring the padding).
+    ///
+    /// If `mid` exceeds the length returns `false`, where `str::split_at` would panic. In that case
+    /// `mid` is clamped to the length, i.e. the first substring is the whole string and the second
+    /// one is empty. As in [`ServerKey::substring`], it's not checked that `mid` is at an UTF-8
+    /// char boundary.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    /// let s = "2024Alice";
+    /// let enc_s = FheString::new(&ck, &s, Some(2));
+    /// let enc_mid = UIntArg::Enc(ck.encrypt_u16(4, None));
+    ///
+    /// let (lhs, rhs, in_bounds) = sk.split_at(&enc_s, &enc_mid);
+    ///
+    /// let lhs_decrypted = ck.decrypt_ascii(&lhs);
+    /// let rhs_decrypted = ck.decrypt_ascii(&rhs);
+    /// let in_bounds = ck.key().decrypt_bool(&in_bounds);
+    ///
+    /// assert_eq!(lhs_decrypted, "2024");
+    /// assert_eq!(rhs_decrypted, "Alice");
+    /// assert!(in_bounds);
+    /// ```
+    pub fn split_at(&self, str: &FheString, mid: &UIntArg) -> (FheString, FheString, BooleanBlock) {
+        let ((lhs, rhs), in_bounds) = rayon::join(
+            || match mid {
+                // With a clear `mid` we can just take the chars at each side
+                UIntArg::Clear(_) => rayon::join(
+                    || self.substring(str, &UIntArg::Clear(0), mid),
+                    || self.substring(str, mid, &UIntArg::Clear(u16::MAX)),
+                ),
+                UIntArg::Enc(_) => {
+                    let index = self.uint_arg_to_index(mid);
+                    let index = self
+                        .key
+                        .scalar_min_parallelized(&index, str.chars().len() as u32);
+
+                    // Splitting at the index of an empty pattern is splitting at the index
+                    let empty_pat = GenericPattern::Clear(ClearString::new(String::new()));
+
+                    self.split_pat_at_index(str, &empty_pat, &index, false)
+                }
+            },
+            || match (self.byte_len(str), mid) {
+                (FheStringLen::NoPadding(len), UIntArg::Clear(mid)) => {
+                    self.key.create_trivial_boolean_block(*mid as usize <= len)
+                }
+                (FheStringLen::NoPadding(len), UIntArg::Enc(mid)) => {
+                    self.key.scalar_le_parallelized(mid.cipher(), len as u32)
+                }
+                (FheStringLen::Padding(len), mid) => {
+                    let mid = self.uint_arg_to_index(mid);
+
+                    self.key.le_parallelized(&mid, &len)
+                }
+            },
+        );
+
+        (lhs, rhs, in_bounds)
+    }
+
     /// Splits the encrypted string into two substrings at the last occurrence of the pattern
     /// (either encrypted or clear) and returns a tuple of the two substrings along with a boolean
     /// indicating if the split occurred.
@@ -73,7 +148,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (s, pat) = (" hello world", " ");
     /// let enc_s = FheString::new(&ck, &s, None);
@@ -140,7 +216,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (s, pat) = (" hello world", " ");
     /// let enc_s = FheString::new(&ck, &s, None);
@@ -196,16 +273,33 @@ impl ServerKey {
         (lhs, rhs, is_match)
     }
 
+    // The number of returned items is bounded by the number of chars in str (plus one or two
+    // depending on the split type). Counting the UTF-8 chars homomorphically is only required when
+    // the pattern can be empty, else we can use the (potentially clear) length in bytes
+    pub(super) fn split_max_counter(
+        &self,
+        str: &FheString,
+        pat: &GenericPattern,
+    ) -> RadixCiphertext {
+        if can_be_empty(pat) {
+            return self.chars_count(str);
+        }
+
+        match self.byte_len(str) {
+            FheStringLen::Padding(enc_val) => enc_val,
+            FheStringLen::NoPadding(val) => self
+                .key
+                .create_trivial_radix(val as u32, self.index_blocks()),
+        }
+    }
+
     fn split_internal(
         &self,
         str: &FheString,
         pat: &GenericPattern,
         split_type: SplitType,
     ) -> SplitInternal {
-        let mut max_counter = match self.len(str) {
-            FheStringLen::Padding(enc_val) => enc_val,
-            FheStringLen::NoPadding(val) => self.key.create_trivial_radix(val as u32, 16),
-        };
+        let mut max_counter = self.split_max_counter(str, pat);
 
         self.key.scalar_add_assign_parallelized(&mut max_counter, 1);
 
@@ -262,10 +356,7 @@ impl ServerKey {
             panic!("Only Split or SplitInclusive")
         }
 
-        let max_counter = match self.len(str) {
-            FheStringLen::Padding(enc_val) => enc_val,
-            FheStringLen::NoPadding(val) => self.key.create_trivial_radix(val as u32, 16),
-        };
+        let max_counter = self.split_max_counter(str, pat);
 
         let internal = SplitInternal {
             split_type,
@@ -281,19 +372,12 @@ impl ServerKey {
     }
 
     fn split_no_leading(&self, str: &FheString, pat: &GenericPattern) -> SplitNoLeading {
-        let mut internal = self.split_internal(str, pat, SplitType::RSplit);
-
-        let prev_return = internal.next(self);
-
-        let leading_empty_str = match self.is_empty(&prev_return.0) {
-            FheStringIsEmpty::Padding(enc) => enc,
-            FheStringIsEmpty::NoPadding(clear) => self.key.create_trivial_boolean_block(clear),
-        };
+        let internal = self.split_internal(str, pat, SplitType::RSplit);
 
         SplitNoLeading {
             internal,
-            prev_return,
-            leading_empty_str,
+            prev_return: None,
+            leading_empty_str: None,
         }
     }
 }
@@ -327,8 +411,9 @@ struct SplitNoTrailing {
 
 struct SplitNoLeading {
     internal: SplitInternal,
-    prev_return: (FheString, BooleanBlock),
-    leading_empty_str: BooleanBlock,
+    // The first item is computed in advance, in the first `next` call
+    prev_return: Option<(FheString, BooleanBlock)>,
+    leading_empty_str: Option<BooleanBlock>,
 }
 
 impl FheStringIterator for SplitInternal {
@@ -338,7 +423,7 @@ impl FheStringIterator for SplitInternal {
             GenericPattern::Enc(pat) => pat.clone(),
         };
 
-        let ((mut index, mut is_some), pat_is_empty) = rayon::join(
+        let ((mut index, mut is_some), empty_pat_step) = rayon::join(
             || {
                 if let SplitType::RSplit = self.split_type {
                     sk.rfind(&self.state, &self.pat)
@@ -346,24 +431,30 @@ impl FheStringIterator for SplitInternal {
                     sk.find(&self.state, &self.pat)
                 }
             },
-            || match sk.is_empty(&trivial_or_enc_pat) {
-                FheStringIsEmpty::Padding(enc) => enc.into_radix(16, &sk.key),
-                FheStringIsEmpty::NoPadding(clear) => sk.key.create_trivial_radix(clear as u32, 16),
+            || {
+                // If pattern is empty and we aren't in the first next call, we add (in the Split
+                // case) or subtract (in the RSplit case) the width of the next char to the index at
+                // which we split the str.
+                //
+                // This is because "ab".split("") returns ["", "a", "b", ""] and, in our case, we
+                // have to manually advance the match index as an empty pattern always matches at
+                // the very start (or end in the rsplit case). With UTF-8 chars we advance by the
+                // char bytes, such that we always split at char boundaries
+                if self.counter == 0 {
+                    return None;
+                }
+
+                let reverse = matches!(self.split_type, SplitType::RSplit);
+
+                sk.empty_pat_char_width(&self.state, &trivial_or_enc_pat, reverse)
             },
         );
 
-        if self.counter > 0 {
-            // If pattern is empty and we aren't in the first next call, we add (in the Split case)
-            // or subtract (in the RSplit case) 1 to the index at which we split the str.
-            //
-            // This is because "ab".split("") returns ["", "a", "b", ""] and, in our case, we have
-            // to manually advance the match index as an empty pattern always matches at the very
-            // start (or end in the rsplit case)
-
+        if let Some(step) = empty_pat_step {
             if let SplitType::RSplit = self.split_type {
-                sk.key.sub_assign_parallelized(&mut index, &pat_is_empty);
+                sk.key.sub_assign_parallelized(&mut index, &step);
             } else {
-                sk.key.add_assign_parallelized(&mut index, &pat_is_empty);
+                sk.key.add_assign_parallelized(&mut index, &step);
             }
         }
 
@@ -488,23 +579,36 @@ impl FheStringIterator for SplitNoLeading {
         // We want to remove the leading empty string i.e. the first returned substring should be
         // skipped if empty.
         //
-        // To achieve that we have computed a next call in advance and conditionally assign values
-        // based on the `trailing_empty_str` flag
+        // To achieve that we compute a next call in advance (in the first call) and conditionally
+        // assign values based on the `leading_empty_str` flag
+
+        if self.prev_return.is_none() {
+            let first = self.internal.next(sk);
+
+            self.leading_empty_str = Some(match sk.is_empty(&first.0) {
+                FheStringIsEmpty::Padding(enc) => enc,
+                FheStringIsEmpty::NoPadding(clear) => sk.key.create_trivial_boolean_block(clear),
+            });
+            self.prev_return = Some(first);
+        }
+
+        let leading_empty_str = self.leading_empty_str.as_ref().unwrap();
+        let prev_return = self.prev_return.as_ref().unwrap();
 
         let (result, is_some) = self.internal.next(sk);
 
         let (return_result, return_is_some) = rayon::join(
-            || sk.conditional_string(&self.leading_empty_str, result.clone(), &self.prev_return.0),
+            || sk.conditional_string(leading_empty_str, result.clone(), &prev_return.0),
             || {
                 let (lhs, rhs) = rayon::join(
                     // This is `is_some` if `leading_empty_str` is true, false otherwise
-                    || sk.key.boolean_bitand(&self.leading_empty_str, &is_some),
+                    || sk.key.boolean_bitand(leading_empty_str, &is_some),
                     // This is the flag from the previous next call if `leading_empty_str` is true,
                     // false otherwise
                     || {
                         sk.key.boolean_bitand(
-                            &sk.key.boolean_bitnot(&self.leading_empty_str),
-                            &self.prev_return.1,
+                            &sk.key.boolean_bitnot(leading_empty_str),
+                            &prev_return.1,
                         )
                     },
                 );
@@ -513,7 +617,7 @@ impl FheStringIterator for SplitNoLeading {
             },
         );
 
-        self.prev_return = (result, is_some);
+        self.prev_return = Some((result, is_some));
 
         (return_result, return_is_some)
     }
@@ -3,7 +3,7 @@ use crate::server_key::pattern::split::{
     SplitInternal, SplitNInternal, SplitNoLeading, SplitNoTrailing, SplitType,
 };
 use crate::server_key::{FheStringIterator, ServerKey};
-use tfhe::integer::BooleanBlock;
+use tfhe::integer::{BooleanBlock, RadixCiphertext};
 
 pub struct RSplit {
     internal: SplitInternal,
@@ -46,7 +46,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (s, pat) = ("hello ", " ");
     ///
@@ -88,7 +89,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (s, pat) = ("hello ", " ");
     ///
@@ -131,7 +133,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (s, pat) = ("hello world", " ");
     ///
@@ -180,7 +183,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (s, pat) = ("hello world", " ");
     ///
@@ -227,7 +231,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (s, pat) = ("hello world ", " ");
     ///
@@ -272,7 +277,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (s, pat) = ("hello world ", " ");
     ///
@@ -316,7 +322,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (s, pat) = ("hello world ", " ");
     ///
@@ -351,40 +358,96 @@ impl FheStringIterator for Split {
     fn next(&mut self, sk: &ServerKey) -> (FheString, BooleanBlock) {
         self.internal.next(sk)
     }
+
+    fn collect_bounded(
+        self,
+        sk: &ServerKey,
+        max_items: usize,
+    ) -> (Vec<(FheString, BooleanBlock)>, RadixCiphertext) {
+        self.internal.collect_items(sk, max_items)
+    }
 }
 
 impl FheStringIterator for RSplit {
     fn next(&mut self, sk: &ServerKey) -> (FheString, BooleanBlock) {
         self.internal.next(sk)
     }
+
+    fn collect_bounded(
+        self,
+        sk: &ServerKey,
+        max_items: usize,
+    ) -> (Vec<(FheString, BooleanBlock)>, RadixCiphertext) {
+        self.internal.collect_items(sk, max_items)
+    }
 }
 
 impl FheStringIterator for SplitN {
     fn next(&mut self, sk: &ServerKey) -> (FheString, BooleanBlock) {
         self.internal.next(sk)
     }
+
+    fn collect_bounded(
+        self,
+        sk: &ServerKey,
+        max_items: usize,
+    ) -> (Vec<(FheString, BooleanBlock)>, RadixCiphertext) {
+        self.internal.collect_items(sk, max_items)
+    }
 }
 
 impl FheStringIterator for RSplitN {
     fn next(&mut self, sk: &ServerKey) -> (FheString, BooleanBlock) {
         self.internal.next(sk)
     }
+
+    fn collect_bounded(
+        self,
+        sk: &ServerKey,
+        max_items: usize,
+    ) -> (Vec<(FheString, BooleanBlock)>, RadixCiphertext) {
+        self.internal.collect_items(sk, max_items)
+    }
 }
 
 impl FheStringIterator for SplitTerminator {
     fn next(&mut self, sk: &ServerKey) -> (FheString, BooleanBlock) {
         self.internal.next(sk)
     }
+
+    fn collect_bounded(
+        self,
+        sk: &ServerKey,
+        max_items: usize,
+    ) -> (Vec<(FheString, BooleanBlock)>, RadixCiphertext) {
+        self.internal.collect_items(sk, max_items)
+    }
 }
 
 impl FheStringIterator for RSplitTerminator {
     fn next(&mut self, sk: &ServerKey) -> (FheString, BooleanBlock) {
         self.internal.next(sk)
     }
+
+    fn collect_bounded(
+        self,
+        sk: &ServerKey,
+        max_items: usize,
+    ) -> (Vec<(FheString, BooleanBlock)>, RadixCiphertext) {
+        self.internal.collect_items(sk, max_items)
+    }
 }
 
 impl FheStringIterator for SplitInclusive {
     fn next(&mut self, sk: &ServerKey) -> (FheString, BooleanBlock) {
         self.internal.next(sk)
     }
+
+    fn collect_bounded(
+        self,
+        sk: &ServerKey,
+        max_items: usize,
+    ) -> (Vec<(FheString, BooleanBlock)>, RadixCiphertext) {
+        self.internal.collect_items(sk, max_items)
+    }
 }
@@ -26,9 +26,9 @@ impl ServerKey {
 
             let is_matched = self.asciis_eq(a.into_iter(), b.into_iter());
 
-            let mut mask = is_matched.clone().into_radix(4, &self.key);
+            let mut mask = is_matched.clone().into_radix(self.char_blocks(), &self.key);
 
-            // If mask == 0u8, it will now be 255u8. If it was 1u8, it will now be 0u8
+            // If mask == 0, it will now have all bits set. If it was 1, it will now be 0
             self.key.scalar_sub_assign_parallelized(&mut mask, 1);
 
             let mutate_chars = if start + pat_len < str_len {
@@ -69,9 +69,9 @@ impl ServerKey {
 
             let is_matched = self.clear_asciis_eq(a.into_iter(), pat);
 
-            let mut mask = is_matched.clone().into_radix(4, &self.key);
+            let mut mask = is_matched.clone().into_radix(self.char_blocks(), &self.key);
 
-            // If mask == 0u8, it will now be 255u8. If it was 1u8, it will now be 0u8
+            // If mask == 0, it will now have all bits set. If it was 1, it will now be 0
             self.key.scalar_sub_assign_parallelized(&mut mask, 1);
 
             let mutate_chars = if start + pat_len < str_len {
@@ -107,7 +107,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (s, prefix, not_prefix) = ("hello world", "hello", "world");
     ///
@@ -147,9 +148,11 @@ impl ServerKey {
 
         let (starts_with, real_pat_len) = rayon::join(
             || self.starts_with(str, pat),
-            || match self.len(&trivial_or_enc_pat) {
+            || match self.byte_len(&trivial_or_enc_pat) {
                 FheStringLen::Padding(enc_val) => enc_val,
-                FheStringLen::NoPadding(val) => self.key.create_trivial_radix(val as u32, 16),
+                FheStringLen::NoPadding(val) => self
+                    .key
+                    .create_trivial_radix(val as u32, self.index_blocks()),
             },
         );
 
@@ -158,7 +161,7 @@ impl ServerKey {
         let shift_left = self.key.if_then_else_parallelized(
             &starts_with,
             &real_pat_len,
-            &self.key.create_trivial_zero_radix(16),
+            &self.key.create_trivial_zero_radix(self.index_blocks()),
         );
 
         result = self.left_shift_chars(str, &shift_left);
@@ -187,7 +190,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let (s, suffix, not_suffix) = ("hello world", "world", "hello");
     ///
@@ -0,0 +1,495 @@
+use crate::ciphertext::{ClearString, FheAsciiChar, FheString, GenericPattern};
+use crate::server_key::{MaybeClear, ServerKey};
+use rayon::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+use tfhe::integer::BooleanBlock;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Token {
+    Literal(u8),
+    // Matches any sequence of chars, like `%` in LIKE or `*` in a glob
+    AnySeq,
+    // Matches a single char, like `_` in LIKE or `?` in a glob
+    AnyChar,
+}
+
+// The wildcard chars and the escape char of each syntax
+struct Syntax {
+    any_seq: u8,
+    any_char: u8,
+    escape: Option<u8>,
+}
+
+// The role of a pattern char, where exactly one of the four is true. A char is skipped if it's an
+// (unescaped) escape char or a padding null
+struct PatChar {
+    skip: MaybeClear,
+    any_seq: MaybeClear,
+    any_char: MaybeClear,
+    literal: MaybeClear,
+    // Whether the literal is equal to each char of str
+    eqs: Vec<MaybeClear>,
+}
+
+fn clear_tokens(pat: &str, syntax: &Syntax) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut bytes = pat.bytes();
+
+    while let Some(byte) = bytes.next() {
+        let token = match byte {
+            // A trailing escape char is ignored
+            byte if Some(byte) == syntax.escape => match bytes.next() {
+                Some(escaped) => Token::Literal(escaped),
+                None => break,
+            },
+            byte if byte == syntax.any_seq => Token::AnySeq,
+            byte if byte == syntax.any_char => Token::AnyChar,
+            byte => Token::Literal(byte),
+        };
+
+        // Consecutive `AnySeq`s are equivalent to a single one
+        if !(token == Token::AnySeq && tokens.last() == Some(&Token::AnySeq)) {
+            tokens.push(token);
+        }
+    }
+
+    tokens
+}
+
+// Returns the literal as a `ClearString` if all the tokens are literals
+fn clear_literal(tokens: &[Token]) -> Option<ClearString> {
+    let bytes = tokens
+        .iter()
+        .map(|token| match token {
+            Token::Literal(byte) => Some(*byte),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    let str = String::from_utf8(bytes).ok()?;
+
+    ClearString::try_new(str).ok()
+}
+
+impl ServerKey {
+    /// Returns `true` if this encrypted string matches the given SQL `LIKE` pattern (either
+    /// encrypted or clear), where `%` matches any sequence of chars (including none) and `_`
+    /// matches exactly one char. The whole string has to match.
+    ///
+    /// If an ASCII `escape` char is provided, the char following it in the pattern is matched
+    /// literally (e.g. `\%` matches `%` with `Some(b'\\')`), and a trailing escape char is
+    /// ignored.
+    ///
+    /// With a clear pattern that only has `%` at the start or end, this is computed with
+    /// [`ServerKey::eq`], [`ServerKey::starts_with`], [`ServerKey::ends_with`] or
+    /// [`ServerKey::contains`]. Otherwise the cost is proportional to the length of the string
+    /// times the length of the pattern.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    /// let s = "ab12c-d";
+    ///
+    /// let enc_s = FheString::new(&ck, &s, Some(2));
+    ///
+    /// let pat = GenericPattern::Enc(FheString::new(&ck, "ab%c_d", Some(1)));
+    /// let result = sk.like(&enc_s, &pat, None);
+    /// assert!(ck.key().decrypt_bool(&result));
+    ///
+    /// let pat = GenericPattern::Clear(ClearString::new(r"ab%c\_d".to_string()));
+    /// let result = sk.like(&enc_s, &pat, Some(b'\\'));
+    /// assert!(!ck.key().decrypt_bool(&result));
+    /// ```
+    pub fn like(&self, str: &FheString, pat: &GenericPattern, escape: Option<u8>) -> BooleanBlock {
+        let syntax = Syntax {
+            any_seq: b'%',
+            any_char: b'_',
+            escape,
+        };
+
+        self.wildcard_match(str, pat, &syntax)
+    }
+
+    /// Returns `true` if this encrypted string matches the given glob pattern (either encrypted or
+    /// clear), where `*` matches any sequence of chars (including none), `?` matches exactly one
+    /// char and `\` escapes the following char. The whole string has to match.
+    ///
+    /// See [`ServerKey::like`] for the details, as this is the same with a different syntax.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    ///
+    /// let enc_s = FheString::new(&ck, "report_2024.pdf", None);
+    /// let pat = GenericPattern::Clear(ClearString::new("report_????.*".to_string()));
+    ///
+    /// let result = sk.glob_match(&enc_s, &pat);
+    /// assert!(ck.key().decrypt_bool(&result));
+    /// ```
+    pub fn glob_match(&self, str: &FheString, pat: &GenericPattern) -> BooleanBlock {
+        let syntax = Syntax {
+            any_seq: b'*',
+            any_char: b'?',
+            escape: Some(b'\\'),
+        };
+
+        self.wildcard_match(str, pat, &syntax)
+    }
+
+    fn wildcard_match(
+        &self,
+        str: &FheString,
+        pat: &GenericPattern,
+        syntax: &Syntax,
+    ) -> BooleanBlock {
+        let pat_chars = match pat {
+            GenericPattern::Clear(pat) => {
+                let tokens = clear_tokens(pat.str(), syntax);
+
+                if let Some(result) = self.clear_wildcard_cases(str, &tokens) {
+                    return result;
+                }
+
+                self.clear_pat_chars(str, &tokens)
+            }
+            GenericPattern::Enc(pat) => self.enc_pat_chars(str, pat, syntax),
+        };
+
+        self.wildcard_dp(str, &pat_chars)
+    }
+
+    // Handles the clear patterns that are a literal with optional `AnySeq`s at the ends
+    fn clear_wildcard_cases(&self, str: &FheString, tokens: &[Token]) -> Option<BooleanBlock> {
+        let (any_at_start, literal, any_at_end) = match tokens {
+            [Token::AnySeq] => return Some(self.key.create_trivial_boolean_block(true)),
+            [Token::AnySeq, middle @ .., Token::AnySeq] => (true, middle, true),
+            [Token::AnySeq, rest @ ..] => (true, rest, false),
+            [rest @ .., Token::AnySeq] => (false, rest, true),
+            rest => (false, rest, false),
+        };
+
+        let literal = GenericPattern::Clear(clear_literal(literal)?);
+
+        let result = match (any_at_start, any_at_end) {
+            (false, false) => self.eq(str, &literal),
+            (false, true) => self.starts_with(str, &literal),
+            (true, false) => self.ends_with(str, &literal),
+            (true, true) => self.contains(str, &literal),
+        };
+
+        Some(result)
+    }
+
+    fn clear_pat_chars(&self, str: &FheString, tokens: &[Token]) -> Vec<PatChar> {
+        let bytes: BTreeSet<u8> = tokens
+            .iter()
+            .filter_map(|token| match token {
+                Token::Literal(byte) => Some(*byte),
+                _ => None,
+            })
+            .collect();
+
+        // Each char of str is only compared once with each literal byte
+        let byte_eqs: BTreeMap<u8, Vec<MaybeClear>> = bytes
+            .into_par_iter()
+            .map(|byte| {
+                let eqs = str
+                    .chars()
+                    .par_iter()
+                    .map(|char| {
+                        MaybeClear::Cipher(self.key.scalar_eq_parallelized(char.ciphertext(), byte))
+                    })
+                    .collect();
+
+                (byte, eqs)
+            })
+            .collect();
+
+        tokens
+            .iter()
+            .map(|token| {
+                let (literal, eqs) = match token {
+                    Token::Literal(byte) => (true, byte_eqs[byte].clone()),
+                    _ => (false, Vec::new()),
+                };
+
+                PatChar {
+                    skip: MaybeClear::Clear(false),
+                    any_seq: MaybeClear::Clear(*token == Token::AnySeq),
+                    any_char: MaybeClear::Clear(*token == Token::AnyChar),
+                    literal: MaybeClear::Clear(literal),
+                    eqs,
+                }
+            })
+            .collect()
+    }
+
+    fn enc_pat_chars(&self, str: &FheString, pat: &FheString, syntax: &Syntax) -> Vec<PatChar> {
+        let scalar_eq = |char: &FheAsciiChar, byte: u8| {
+            MaybeClear::Cipher(self.key.scalar_eq_parallelized(char.ciphertext(), byte))
+        };
+
+        // For each pattern char, whether it's the escape char, each wildcard and a null, as well as
+        // whether it's equal to each char of str
+        let flags: Vec<_> = pat
+            .chars()
+            .par_iter()
+            .map(|pat_char| {
+                let eqs = str
+                    .chars()
+                    .par_iter()
+                    .map(|char| {
+                        let is_eq = self
+                            .key
+                            .eq_parallelized(char.ciphertext(), pat_char.ciphertext());
+
+                        MaybeClear::Cipher(is_eq)
+                    })
+                    .collect();
+
+                let is_escape = match syntax.escape {
+                    Some(escape) => scalar_eq(pat_char, escape),
+                    None => MaybeClear::Clear(false),
+                };
+
+                let is_null = if pat.is_padded() {
+                    scalar_eq(pat_char, 0)
+                } else {
+                    MaybeClear::Clear(false)
+                };
+
+                let is_any_seq = scalar_eq(pat_char, syntax.any_seq);
+                let is_any_char = scalar_eq(pat_char, syntax.any_char);
+
+                (is_escape, is_any_seq, is_any_char, is_null, eqs)
+            })
+            .collect();
+
+        // Whether the previous pattern char is an escape char that isn't escaped itself
+        let mut is_escaped = MaybeClear::Clear(false);
+
+        flags
+            .into_iter()
+            .map(|(is_escape, is_any_seq, is_any_char, is_null, eqs)| {
+                let not_escaped = self.maybe_clear_not(&is_escaped);
+
+                let escapes_next = self.maybe_clear_and(&is_escape, &not_escaped);
+                let skip = self.maybe_clear_or(&is_null, &escapes_next);
+                let any_seq = self.maybe_clear_and(&is_any_seq, &not_escaped);
+                let any_char = self.maybe_clear_and(&is_any_char, &not_escaped);
+
+                let not_literal = self.maybe_clear_or(&skip, &any_seq);
+                let not_literal = self.maybe_clear_or(&not_literal, &any_char);
+                let literal = self.maybe_clear_not(&not_literal);
+
+                is_escaped = escapes_next;
+
+                PatChar {
+                    skip,
+                    any_seq,
+                    any_char,
+                    literal,
+                    eqs,
+                }
+            })
+            .collect()
+    }
+
+    // Dynamic programming over the chars of str, where after consuming `i` chars `dp[j]` is whether
+    // they match the first `j` pattern chars
+    fn wildcard_dp(&self, str: &FheString, pat_chars: &[PatChar]) -> BooleanBlock {
+        let chars = str.chars();
+        let str_len = chars.len();
+        let pat_len = pat_chars.len();
+
+        // We only need the UTF-8 char boundaries if a pattern char may be `AnyChar`
+        let needs_boundaries = pat_chars
+            .iter()
+            .any(|pat_char| !matches!(pat_char.any_char, MaybeClear::Clear(false)));
+
+        // For each char, whether it's a continuation byte and whether it's the first byte of a
+        // char (i.e. neither a continuation byte nor a null), and for each index whether it's the
+        // end of str
+        let (boundaries, is_end) = rayon::join(
+            || {
+                chars
+                    .par_iter()
+                    .map(|char| {
+                        if !needs_boundaries {
+                            return (MaybeClear::Clear(false), MaybeClear::Clear(false));
+                        }
+
+                        let ((ge, le), is_null) = rayon::join(
+                            || {
+                                rayon::join(
+                                    || self.key.scalar_ge_parallelized(char.ciphertext(), 0x80u8),
+                                    || self.key.scalar_le_parallelized(char.ciphertext(), 0xBFu8),
+                                )
+                            },
+                            || self.key.scalar_eq_parallelized(char.ciphertext(), 0u8),
+                        );
+
+                        let is_cont = self.key.boolean_bitand(&ge, &le);
+                        let not_first = self.key.boolean_bitor(&is_cont, &is_null);
+                        let is_first = self.key.boolean_bitnot(&not_first);
+
+                        (MaybeClear::Cipher(is_cont), MaybeClear::Cipher(is_first))
+                    })
+                    .collect::<Vec<_>>()
+            },
+            || {
+                (0..str_len + 1)
+                    .into_par_iter()
+                    .map(|i| match chars.get(i) {
+                        Some(char) if str.is_padded() => {
+                            let is_null = self.key.scalar_eq_parallelized(char.ciphertext(), 0u8);
+                            MaybeClear::Cipher(is_null)
+                        }
+                        Some(_) => MaybeClear::Clear(false),
+                        None => MaybeClear::Clear(true),
+                    })
+                    .collect::<Vec<_>>()
+            },
+        );
+        let (is_cont, is_first): (Vec<_>, Vec<_>) = boundaries.into_iter().unzip();
+
+        // Without consuming any char we can only match skipped chars and `AnySeq`s
+        let mut dp = vec![MaybeClear::Clear(true)];
+        for (j, pat_char) in pat_chars.iter().enumerate() {
+            let matches_empty = self.maybe_clear_or(&pat_char.skip, &pat_char.any_seq);
+            dp.push(self.maybe_clear_and(&dp[j], &matches_empty));
+        }
+
+        // `in_char[j]` is whether the last consumed bytes are the start of a single char that is
+        // matched by the `AnyChar` at `j - 1`
+        let mut in_char = vec![MaybeClear::Clear(false); pat_len + 1];
+
+        let mut result = self.maybe_clear_and(&dp[pat_len], &is_end[0]);
+
+        for i in 0..str_len {
+            // A char ends at `i` if the next byte is not a continuation byte
+            let char_ends = match is_cont.get(i + 1) {
+                Some(is_cont_next) => self.maybe_clear_not(is_cont_next),
+                None => MaybeClear::Clear(true),
+            };
+
+            let mut new_dp = vec![MaybeClear::Clear(false)];
+            let mut new_in_char = vec![MaybeClear::Clear(false)];
+
+            for (j, pat_char) in pat_chars.iter().enumerate() {
+                let skip = self.maybe_clear_and(&pat_char.skip, &new_dp[j]);
+
+                let any_seq = {
+                    let prev = self.maybe_clear_or(&new_dp[j], &dp[j + 1]);
+                    self.maybe_clear_and(&pat_char.any_seq, &prev)
+                };
+
+                let any_char = if let MaybeClear::Clear(false) = pat_char.any_char {
+                    new_in_char.push(MaybeClear::Clear(false));
+                    MaybeClear::Clear(false)
+                } else {
+                    // Either we start a new char or we continue the current one
+                    let starts = self.maybe_clear_and(&dp[j], &is_first[i]);
+                    let continues = self.maybe_clear_and(&in_char[j + 1], &is_cont[i]);
+                    let is_in_char = self.maybe_clear_or(&starts, &continues);
+
+                    let is_char = self.maybe_clear_and(&is_in_char, &char_ends);
+                    new_in_char.push(self.maybe_clear_and(&pat_char.any_char, &is_in_char));
+
+                    self.maybe_clear_and(&pat_char.any_char, &is_char)
+                };
+
+                let literal = if let MaybeClear::Clear(false) = pat_char.literal {
+                    MaybeClear::Clear(false)
+                } else {
+                    let is_eq = self.maybe_clear_and(&dp[j], &pat_char.eqs[i]);
+                    self.maybe_clear_and(&pat_char.literal, &is_eq)
+                };
+
+                let lhs = self.maybe_clear_or(&skip, &any_seq);
+                let rhs = self.maybe_clear_or(&any_char, &literal);
+                new_dp.push(self.maybe_clear_or(&lhs, &rhs));
+            }
+
+            dp = new_dp;
+            in_char = new_in_char;
+
+            let is_match = self.maybe_clear_and(&dp[pat_len], &is_end[i + 1]);
+            result = self.maybe_clear_or(&result, &is_match);
+        }
+
+        result.into_boolean_block(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ciphertext::{ClearString, FheString, GenericPattern};
+    use crate::server_key::gen_keys;
+
+    #[test]
+    fn test_like_and_glob() {
+        let (ck, sk) = gen_keys();
+
+        // (str, padding, LIKE pattern, padding, expected)
+        let test_cases = [
+            ("", 0, "", 0, true),
+            ("", 1, "%", 1, true),
+            ("", 0, "_", 0, false),
+            ("abc", 1, "abc", 1, true),
+            ("abc", 0, "a%", 0, true),
+            ("abc", 2, "%bc", 0, true),
+            ("abc", 0, "%b%", 2, true),
+            ("abc", 1, "a_c", 0, true),
+            ("abc", 0, "a_", 1, false),
+            ("añc", 1, "a_c", 1, true),
+            ("añc", 0, "a__c", 0, false),
+            ("ñ", 2, "%_", 0, true),
+            ("ab%c", 1, r"%\%c", 0, true),
+            ("abxc", 0, r"%\%c", 1, false),
+            ("a_b", 0, r"a\_b", 0, true),
+            ("axb", 2, r"a\_b", 1, false),
+            (r"a\b", 0, r"a\\b", 0, true),
+            ("ab", 0, r"ab\", 1, true),
+            ("xaybzc", 1, "%a%b_c", 1, true),
+            ("xaybzc", 0, "%a%b_", 0, false),
+        ];
+
+        for (str, str_pad, pat, pat_pad, expected) in test_cases {
+            let enc_str = FheString::new_utf8(&ck, str, Some(str_pad));
+
+            let clear_pat = GenericPattern::Clear(ClearString::new(pat.to_string()));
+            let enc_pat = GenericPattern::Enc(FheString::new(&ck, pat, Some(pat_pad)));
+
+            for generic_pat in [clear_pat, enc_pat] {
+                let result = sk.like(&enc_str, &generic_pat, Some(b'\\'));
+
+                assert_eq!(ck.key().decrypt_bool(&result), expected, "{str} LIKE {pat}");
+            }
+
+            // The same pattern in glob syntax
+            let glob = pat.replace('%', "*").replace('_', "?");
+            let enc_glob = GenericPattern::Enc(FheString::new(&ck, &glob, Some(pat_pad)));
+
+            let result = sk.glob_match(&enc_str, &enc_glob);
+
+            assert_eq!(
+                ck.key().decrypt_bool(&result),
+                expected,
+                "{str} GLOB {glob}"
+            );
+        }
+
+        // Without an escape char the backslash is a literal
+        let enc_str = FheString::new(&ck, r"a\x", None);
+        let pat = GenericPattern::Clear(ClearString::new(r"a\_".to_string()));
+
+        let result = sk.like(&enc_str, &pat, None);
+
+        assert!(ck.key().decrypt_bool(&result));
+    }
+}
@@ -0,0 +1,232 @@
+use crate::ciphertext::{FheAsciiChar, FheString};
+use crate::server_key::ServerKey;
+use tfhe::integer::BooleanBlock;
+
+/// Policy used by [`ServerKey::resize_to_bucket`] to choose the capacity of a string from its
+/// ciphertext length (i.e. the number of [`FheAsciiChar`]s, including padding).
+///
+/// All strings whose ciphertext lengths fall in the same bucket end up with the same capacity, so
+/// the result only leaks the bucket rather than the exact length.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LengthBucket {
+    /// The smallest power of two that is greater or equal to the length.
+    PowerOfTwo,
+    /// The smallest multiple of the provided value that is greater or equal to the length.
+    ///
+    /// Panics (when used) if the provided value is zero.
+    MultipleOf(usize),
+}
+
+impl LengthBucket {
+    /// Returns the capacity of the bucket that contains `len`, which is never smaller than `len`.
+    pub fn capacity(self, len: usize) -> usize {
+        match self {
+            LengthBucket::PowerOfTwo => len.next_power_of_two(),
+            LengthBucket::MultipleOf(multiple) => len.next_multiple_of(multiple),
+        }
+    }
+}
+
+impl ServerKey {
+    /// Pads or truncates the encrypted string such that it has exactly `capacity`
+    /// [`FheAsciiChar`]s, returning the result and an encrypted `BooleanBlock` that is true if no
+    /// char was lost (i.e. the string fits in the capacity).
+    ///
+    /// When the string is shorter it's padded with nulls. When it's longer it's truncated: the
+    /// last char of the result is always a null (as the true length is unknown if the string is
+    /// padded) and at most `capacity - 1` chars are kept. Truncating is only safe if the length is
+    /// known to fit, or if clamping the string is acceptable, which can be checked with the
+    /// returned `BooleanBlock`. If the cut lands inside a UTF-8 char, the bytes of that char that
+    /// would be kept are replaced by nulls, so the result is always valid UTF-8.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    ///
+    /// let enc_s = FheString::new(&ck, "hello", Some(3));
+    ///
+    /// let (padded, fits) = sk.resize_to(&enc_s, 10);
+    /// assert_eq!(padded.chars().len(), 10);
+    /// assert_eq!(ck.decrypt_ascii(&padded), "hello");
+    /// assert!(ck.key().decrypt_bool(&fits));
+    ///
+    /// let (truncated, fits) = sk.resize_to(&enc_s, 6);
+    /// assert_eq!(truncated.chars().len(), 6);
+    /// assert_eq!(ck.decrypt_ascii(&truncated), "hello");
+    /// assert!(ck.key().decrypt_bool(&fits));
+    ///
+    /// let (clamped, fits) = sk.resize_to(&enc_s, 4);
+    /// assert_eq!(ck.decrypt_ascii(&clamped), "hel");
+    /// assert!(!ck.key().decrypt_bool(&fits));
+    /// ```
+    pub fn resize_to(&self, str: &FheString, capacity: usize) -> (FheString, BooleanBlock) {
+        let str_len = str.chars().len();
+        let mut result = str.clone();
+
+        if str_len <= capacity {
+            if str_len < capacity {
+                let nulls = (str_len..capacity).map(|_| FheAsciiChar::null(self));
+
+                result.chars_vec().extend(nulls);
+                result.set_is_padded(true);
+            }
+
+            return (result, self.key.create_trivial_boolean_block(true));
+        }
+
+        // We keep `capacity - 1` chars and a null, so the result is padded even if some of the
+        // kept bytes are cleared by `clear_partial_char`. Without padding we know that the
+        // truncated chars aren't null. With padding, as nulls can only be at the end, the string
+        // fits if the first removed char is null
+        let kept = capacity.saturating_sub(1);
+        let fits = if str.is_padded() {
+            self.key
+                .scalar_eq_parallelized(str.chars()[kept].ciphertext(), 0u8)
+        } else {
+            self.key.create_trivial_boolean_block(false)
+        };
+
+        result.chars_vec().truncate(kept);
+        self.clear_partial_char(str, &mut result, kept);
+
+        if capacity == 0 {
+            result.set_is_padded(false);
+        } else {
+            result.append_null(self);
+        }
+
+        (result, fits)
+    }
+
+    // Replaces by nulls the bytes of `result` (i.e. `str` truncated at `cut`) that come after the
+    // last char boundary of `str` at or before `cut`. As a char takes at most 4 bytes, only the
+    // boundaries at the last 3 indices up to `cut` are needed, which only depend on the bytes
+    // around them. The cleared bytes are at the end of `result`, so the caller has to make it
+    // padded
+    fn clear_partial_char(&self, str: &FheString, result: &mut FheString, cut: usize) {
+        if cut == 0 {
+            return;
+        }
+
+        let start = cut.saturating_sub(3);
+
+        let mut window = FheString::empty();
+        window
+            .chars_vec()
+            .extend_from_slice(&str.chars()[start..=cut]);
+        window.set_is_padded(str.is_padded());
+
+        let boundaries = self.char_boundaries(&window);
+
+        // A byte is kept if there's a boundary after it, at or before the cut
+        let mut is_kept = boundaries[cut - start].clone();
+        for i in (start..cut).rev() {
+            let char = &mut result.chars_mut()[i];
+
+            *char.ciphertext_mut() = self.key.if_then_else_parallelized(
+                &is_kept,
+                char.ciphertext(),
+                &self.key.create_trivial_zero_radix(self.char_blocks()),
+            );
+
+            if i > start {
+                self.key
+                    .boolean_bitor_assign(&mut is_kept, &boundaries[i - start]);
+            }
+        }
+    }
+
+    /// Pads the encrypted string with nulls up to the capacity given by the [`LengthBucket`] for
+    /// its ciphertext length. As the capacity is never smaller than the length, no char is lost.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    ///
+    /// let enc_s = FheString::new(&ck, "hello", Some(1));
+    ///
+    /// let result = sk.resize_to_bucket(&enc_s, LengthBucket::PowerOfTwo);
+    /// assert_eq!(result.chars().len(), 8);
+    ///
+    /// let result = sk.resize_to_bucket(&enc_s, LengthBucket::MultipleOf(5));
+    /// assert_eq!(result.chars().len(), 10);
+    /// assert_eq!(ck.decrypt_ascii(&result), "hello");
+    /// ```
+    pub fn resize_to_bucket(&self, str: &FheString, bucket: LengthBucket) -> FheString {
+        let capacity = bucket.capacity(str.chars().len());
+
+        let (result, _) = self.resize_to(str, capacity);
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server_key::gen_keys;
+
+    #[test]
+    fn test_resize() {
+        let (ck, sk) = gen_keys();
+
+        // (str, padding, capacity, expected, fits)
+        let test_cases = [
+            ("", 0, 0, "", true),
+            ("", 2, 0, "", true),
+            ("abc", 0, 3, "abc", true),
+            ("abc", 0, 5, "abc", true),
+            ("abc", 0, 2, "a", false),
+            ("abcd", 0, 3, "ab", false),
+            ("abc", 2, 4, "abc", true),
+            ("abc", 2, 3, "ab", false),
+            ("abc", 2, 0, "", false),
+        ];
+
+        for (str, pad, capacity, expected, expected_fits) in test_cases {
+            let enc_str = FheString::new(&ck, str, Some(pad));
+
+            let (result, fits) = sk.resize_to(&enc_str, capacity);
+
+            assert_eq!(result.chars().len(), capacity);
+            assert_eq!(ck.decrypt_ascii(&result), expected);
+            assert_eq!(ck.key().decrypt_bool(&fits), expected_fits);
+        }
+
+        // "ú" takes 2 bytes and "東" takes 3, so these cuts land inside a char
+        let utf8_cases = [
+            ("Ñandú", 0, 6, "Ñand"),
+            ("Ñandú", 0, 2, ""),
+            ("Ñandú", 1, 7, "Ñand"),
+            ("Ñandú", 2, 8, "Ñandú"),
+            ("a東", 0, 3, "a"),
+            ("a東", 0, 2, "a"),
+            ("東", 1, 3, ""),
+        ];
+
+        for (str, pad, capacity, expected) in utf8_cases {
+            let enc_str = FheString::new_utf8(&ck, str, Some(pad));
+
+            let (result, _) = sk.resize_to(&enc_str, capacity);
+
+            assert_eq!(result.chars().len(), capacity);
+            assert_eq!(ck.decrypt_utf8(&result), expected);
+        }
+
+        let capacities: Vec<_> = [0, 1, 5, 8, 9]
+            .into_iter()
+            .map(|len| {
+                (
+                    LengthBucket::PowerOfTwo.capacity(len),
+                    LengthBucket::MultipleOf(4).capacity(len),
+                )
+            })
+            .collect();
+
+        assert_eq!(capacities, [(1, 0), (1, 4), (8, 8), (8, 8), (16, 12)]);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::ciphertext::FheString;
+use crate::server_key::ServerKey;
+use rayon::prelude::*;
+
+impl ServerKey {
+    /// Returns the canonical form of an encrypted string that may not satisfy the padding
+    /// invariants, for instance one deserialized from an untrusted source.
+    ///
+    /// All algorithms assume that nulls can only be at the end of the string, and that the last
+    /// char is null if the string is padded. This function guarantees both homomorphically:
+    /// every char after the first null is set to null (i.e. the string is truncated at its first
+    /// null), and a null is appended such that the result is always padded. Each char is also
+    /// resized to the expected number of blocks.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    ///
+    /// // The padding flag is set but there's no trailing null
+    /// let mut enc_s = FheString::new(&ck, "hello", None);
+    /// enc_s.set_is_padded(true);
+    ///
+    /// let result = sk.sanitize(&enc_s);
+    /// let sanitized = ck.decrypt_ascii(&result);
+    ///
+    /// assert_eq!(sanitized, "hello");
+    /// ```
+    pub fn sanitize(&self, str: &FheString) -> FheString {
+        let mut result = str.clone();
+
+        if result.chars().is_empty() {
+            result.set_is_padded(false);
+            return result;
+        }
+
+        result.chars_mut().par_iter_mut().for_each(|char| {
+            self.pad_or_trim_ciphertext(char.ciphertext_mut(), self.char_blocks());
+        });
+
+        let is_null: Vec<_> = result
+            .chars()
+            .par_iter()
+            .map(|char| self.key.scalar_eq_parallelized(char.ciphertext(), 0u8))
+            .collect();
+
+        // `null_found[i]` is true if there's a null at any index up to i
+        let mut null_found = Vec::with_capacity(is_null.len());
+        let mut prev_found = self.key.create_trivial_boolean_block(false);
+
+        for is_null in is_null {
+            prev_found = self.key.boolean_bitor(&prev_found, &is_null);
+            null_found.push(prev_found.clone());
+        }
+
+        let zero = self.key.create_trivial_zero_radix(self.char_blocks());
+
+        result
+            .chars_mut()
+            .par_iter_mut()
+            .zip(null_found)
+            .for_each(|(char, null_found)| {
+                let sanitized =
+                    self.key
+                        .if_then_else_parallelized(&null_found, &zero, char.ciphertext());
+
+                *char.ciphertext_mut() = sanitized;
+            });
+
+        // We cannot trust the padding flag nor know if the last char is null, so we always make
+        // the result padded
+        result.append_null(self);
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ciphertext::FheString;
+    use crate::server_key::gen_keys;
+
+    #[test]
+    fn test_sanitize() {
+        let (ck, sk) = gen_keys();
+
+        // "ab\0c" and "\0\0ab", with nulls that aren't only at the end and the padding flag unset
+        for (val, expected) in [
+            (0x6162_0063u32, "ab"),
+            (0x0000_6162, ""),
+            (0x6162_6364, "abcd"),
+        ] {
+            let str = FheString::from_uint(ck.key().encrypt_radix(val, 16));
+
+            let result = sk.sanitize(&str);
+
+            assert!(result.is_padded());
+            assert_eq!(ck.decrypt_ascii(&result), expected);
+        }
+
+        let mut str = FheString::new(&ck, "abc", Some(2));
+        str.set_is_padded(false);
+
+        assert_eq!(ck.decrypt_ascii(&sk.sanitize(&str)), "abc");
+        assert_eq!(ck.decrypt_ascii(&sk.sanitize(&FheString::empty())), "");
+    }
+}
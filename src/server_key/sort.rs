@@ -0,0 +1,278 @@
+use crate::ciphertext::FheString;
+use crate::server_key::ServerKey;
+use rayon::prelude::*;
+use tfhe::integer::BooleanBlock;
+
+// The comparators of Batcher's odd-even merge sort, grouped in layers of disjoint pairs. When the
+// length isn't a power of two we drop the comparators with an out of bounds index, which is the
+// same as sorting with extra elements at the end that are greater than any other
+fn odd_even_merge_layers(len: usize) -> Vec<Vec<(usize, usize)>> {
+    let mut layers = Vec::new();
+
+    let mut p = 1;
+    while p < len {
+        let mut k = p;
+
+        while k > 0 {
+            let mut layer = Vec::new();
+
+            let mut j = k % p;
+            while j + k < len {
+                for i in 0..k.min(len - j - k) {
+                    // Only compare elements within the same pair of sorted runs of length p
+                    if (i + j) / (2 * p) == (i + j + k) / (2 * p) {
+                        layer.push((i + j, i + j + k));
+                    }
+                }
+
+                j += 2 * k;
+            }
+
+            layers.push(layer);
+            k /= 2;
+        }
+
+        p *= 2;
+    }
+
+    layers
+}
+
+impl ServerKey {
+    // Returns whether `rhs` is less than `lhs`, i.e. if they have to be swapped
+    fn must_swap(&self, lhs: &FheString, rhs: &FheString) -> BooleanBlock {
+        self.enc_lt(rhs, lhs)
+    }
+
+    fn swap_if(&self, swap: &BooleanBlock, lhs: &FheString, rhs: &FheString) -> [FheString; 2] {
+        let (new_lhs, new_rhs) = rayon::join(
+            || self.conditional_string(swap, rhs.clone(), lhs),
+            || self.conditional_string(swap, lhs.clone(), rhs),
+        );
+
+        [new_lhs, new_rhs]
+    }
+
+    // Runs the sorting network over the keys, applying the same swaps to the payload (if not empty)
+    fn sort_network(&self, keys: &mut [FheString], payload: &mut [FheString]) {
+        for layer in odd_even_merge_layers(keys.len()) {
+            let swapped: Vec<_> = layer
+                .par_iter()
+                .map(|&(i, j)| {
+                    let swap = self.must_swap(&keys[i], &keys[j]);
+
+                    rayon::join(
+                        || self.swap_if(&swap, &keys[i], &keys[j]),
+                        || {
+                            (!payload.is_empty())
+                                .then(|| self.swap_if(&swap, &payload[i], &payload[j]))
+                        },
+                    )
+                })
+                .collect();
+
+            for (&(i, j), ([key_i, key_j], payload_pair)) in layer.iter().zip(swapped) {
+                (keys[i], keys[j]) = (key_i, key_j);
+
+                if let Some([payload_i, payload_j]) = payload_pair {
+                    (payload[i], payload[j]) = (payload_i, payload_j);
+                }
+            }
+        }
+    }
+
+    /// Sorts a slice of encrypted strings in lexicographic order, like `slice::sort_unstable`.
+    ///
+    /// The strings are sorted with a sorting network, i.e. a fixed sequence of comparisons and
+    /// conditional swaps (using [`ServerKey::lt`]), which takes `O(n log² n)` comparisons and
+    /// reveals nothing about the order. The sort is not stable. As a swap selects between two
+    /// strings, each sorted string takes the ciphertext length of the longest one (with padding).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    /// let names = ["Carol", "alice", "Bob", "Alice"];
+    ///
+    /// let mut enc_names: Vec<_> = names
+    ///     .iter()
+    ///     .map(|name| FheString::new(&ck, name, Some(2)))
+    ///     .collect();
+    ///
+    /// sk.sort(&mut enc_names);
+    ///
+    /// let sorted: Vec<_> = enc_names.iter().map(|name| ck.decrypt_ascii(name)).collect();
+    ///
+    /// assert_eq!(sorted, ["Alice", "Bob", "Carol", "alice"]);
+    /// ```
+    pub fn sort(&self, strs: &mut [FheString]) {
+        self.sort_network(strs, &mut []);
+    }
+
+    /// Sorts a slice of encrypted keys in lexicographic order, applying the same permutation to
+    /// the encrypted strings of `payload` (e.g. the other column of each row). See
+    /// [`ServerKey::sort`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `keys` and `payload` have different lengths.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    ///
+    /// let enc = |s| FheString::new(&ck, s, None);
+    ///
+    /// let mut names = vec![enc("Bob"), enc("Alice")];
+    /// let mut cities = vec![enc("Paris"), enc("Oslo")];
+    ///
+    /// sk.sort_by_key(&mut names, &mut cities);
+    ///
+    /// assert_eq!(ck.decrypt_ascii(&names[0]), "Alice");
+    /// assert_eq!(ck.decrypt_ascii(&cities[0]), "Oslo");
+    /// ```
+    pub fn sort_by_key(&self, keys: &mut [FheString], payload: &mut [FheString]) {
+        assert_eq!(
+            keys.len(),
+            payload.len(),
+            "keys and payload should have the same length"
+        );
+
+        self.sort_network(keys, payload);
+    }
+
+    /// Returns the lexicographically smallest of the encrypted strings, or `None` if the slice is
+    /// empty.
+    ///
+    /// The strings are compared in a balanced tree of [`ServerKey::lt`] comparisons, so there are
+    /// `n - 1` comparisons in `O(log n)` sequential steps.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    ///
+    /// let enc_strs: Vec<_> = ["pear", "apple", "fig"]
+    ///     .iter()
+    ///     .map(|s| FheString::new(&ck, s, Some(1)))
+    ///     .collect();
+    ///
+    /// let min = sk.min(&enc_strs).unwrap();
+    ///
+    /// assert_eq!(ck.decrypt_ascii(&min), "apple");
+    /// ```
+    pub fn min(&self, strs: &[FheString]) -> Option<FheString> {
+        strs.par_iter().cloned().reduce_with(|lhs, rhs| {
+            let swap = self.must_swap(&lhs, &rhs);
+
+            self.conditional_string(&swap, rhs, &lhs)
+        })
+    }
+
+    /// Returns the lexicographically greatest of the encrypted strings, or `None` if the slice is
+    /// empty. See [`ServerKey::min`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    ///
+    /// let enc_strs: Vec<_> = ["pear", "apple", "fig"]
+    ///     .iter()
+    ///     .map(|s| FheString::new(&ck, s, None))
+    ///     .collect();
+    ///
+    /// let max = sk.max(&enc_strs).unwrap();
+    ///
+    /// assert_eq!(ck.decrypt_ascii(&max), "pear");
+    /// ```
+    pub fn max(&self, strs: &[FheString]) -> Option<FheString> {
+        strs.par_iter().cloned().reduce_with(|lhs, rhs| {
+            let swap = self.must_swap(&lhs, &rhs);
+
+            self.conditional_string(&swap, lhs, &rhs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::odd_even_merge_layers;
+    use crate::ciphertext::FheString;
+    use crate::server_key::gen_keys;
+
+    #[test]
+    fn test_sorting_network() {
+        // The network sorts every sequence of 0s and 1s (zero-one principle)
+        for len in 0..10 {
+            let layers = odd_even_merge_layers(len);
+
+            for bits in 0..1u32 << len {
+                let mut values: Vec<_> = (0..len).map(|i| (bits >> i) & 1).collect();
+
+                for layer in &layers {
+                    for &(i, j) in layer {
+                        if values[j] < values[i] {
+                            values.swap(i, j);
+                        }
+                    }
+                }
+
+                assert!(
+                    values.windows(2).all(|pair| pair[0] <= pair[1]),
+                    "{len} {bits}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_sort() {
+        let (ck, sk) = gen_keys();
+
+        // (str, padding)
+        let strs = [
+            ("banana", 0),
+            ("", 2),
+            ("apple", 1),
+            ("bananas", 0),
+            ("Zebra", 3),
+            ("apple", 0),
+            ("", 0),
+        ];
+
+        let mut enc_strs: Vec<_> = strs
+            .iter()
+            .map(|(str, pad)| FheString::new(&ck, str, Some(*pad)))
+            .collect();
+        let mut enc_payload: Vec<_> = (0..strs.len())
+            .map(|i| FheString::new(&ck, &i.to_string(), None))
+            .collect();
+
+        let min = sk.min(&enc_strs).unwrap();
+        let max = sk.max(&enc_strs).unwrap();
+
+        assert_eq!(ck.decrypt_ascii(&min), "");
+        assert_eq!(ck.decrypt_ascii(&max), "bananas");
+        assert!(sk.min(&[]).is_none());
+
+        sk.sort_by_key(&mut enc_strs, &mut enc_payload);
+
+        let mut expected: Vec<_> = strs.iter().map(|(str, _)| *str).collect();
+        expected.sort();
+
+        let sorted: Vec<_> = enc_strs.iter().map(|s| ck.decrypt_ascii(s)).collect();
+        assert_eq!(sorted, expected);
+
+        // Each payload is still next to its key
+        for (key, payload) in sorted.iter().zip(&enc_payload) {
+            let index: usize = ck.decrypt_ascii(payload).parse().unwrap();
+            assert_eq!(strs[index].0, key);
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use crate::ciphertext::{FheString, UIntArg};
+use crate::client_key::EncU16;
+use crate::server_key::{FheStringLen, ServerKey};
+use tfhe::integer::{BooleanBlock, RadixCiphertext};
+
+impl ServerKey {
+    /// Converts an encrypted index or length, like the ones returned by [`ServerKey::find`] or
+    /// [`ServerKey::byte_len`], into an [`EncU16`] that can be used as an [`UIntArg`].
+    ///
+    /// Only the least significant blocks are kept, so the value is assumed to fit in a u16.
+    pub fn index_to_u16(&self, index: &RadixCiphertext) -> EncU16 {
+        let mut cipher = index.clone();
+        self.pad_or_trim_ciphertext(&mut cipher, self.u16_blocks());
+
+        EncU16::from_cipher(cipher, None)
+    }
+
+    /// Returns the substring of an encrypted string between the `start` (inclusive) and `end`
+    /// (exclusive) byte indices, like `&str[start..end]` but clamping both indices to the length of
+    /// the string. If `start >= end` the result is empty.
+    ///
+    /// The indices can be clear or encrypted. An encrypted index returned by a function like
+    /// [`ServerKey::find`] can be converted with [`ServerKey::index_to_u16`]. Note that, unlike the
+    /// clear str API, it's not checked that the indices are at UTF-8 char boundaries.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    /// let s = "name: Alice";
+    ///
+    /// let enc_s = FheString::new(&ck, &s, Some(2));
+    /// let label = GenericPattern::Clear(ClearString::new("name: ".to_string()));
+    ///
+    /// // Extract the text after the label
+    /// let (index, _) = sk.find(&enc_s, &label);
+    /// let start = sk.key().scalar_add_parallelized(&index, 6u32);
+    /// let start = UIntArg::Enc(sk.index_to_u16(&start));
+    ///
+    /// let result = sk.substring(&enc_s, &start, &UIntArg::Clear(u16::MAX));
+    /// let substring = ck.decrypt_ascii(&result);
+    ///
+    /// assert_eq!(substring, "Alice");
+    /// ```
+    pub fn substring(&self, str: &FheString, start: &UIntArg, end: &UIntArg) -> FheString {
+        let str_len = str.chars().len();
+
+        if let (UIntArg::Clear(start), UIntArg::Clear(end)) = (start, end) {
+            let end = (*end as usize).min(str_len);
+            let start = (*start as usize).min(end);
+
+            let mut result = FheString::empty();
+            result
+                .chars_vec()
+                .extend_from_slice(&str.chars()[start..end]);
+
+            // If str is padded the substring may contain nulls, so we add a null at the end as we
+            // cannot assume it isn't padded
+            if str.is_padded() && start != end {
+                result.append_null(self);
+            }
+
+            return result;
+        }
+
+        let (start, end) = rayon::join(
+            || self.uint_arg_to_index(start),
+            || self.uint_arg_to_index(end),
+        );
+
+        self.substring_between(str, &start, &end)
+    }
+
+    // Returns the substring between the encrypted `start` and `end` indices, clamped to the length
+    // of the string, as in `ServerKey::substring`
+    pub(crate) fn substring_between(
+        &self,
+        str: &FheString,
+        start: &RadixCiphertext,
+        end: &RadixCiphertext,
+    ) -> FheString {
+        let str_len = str.chars().len();
+        if str_len == 0 {
+            return FheString::empty();
+        }
+
+        // Clamping both indices also ensures that the shifts below fit in the string blocks
+        let (start, end) = rayon::join(
+            || self.key.scalar_min_parallelized(start, str_len as u32),
+            || self.key.scalar_min_parallelized(end, str_len as u32),
+        );
+
+        let str_len = self
+            .key
+            .create_trivial_radix(str_len as u32, self.index_blocks());
+
+        // Shifting right by `str_len - end` removes the chars from `end` onwards, then shifting
+        // left by that amount plus `start` removes the chars before `start`. If `start >= end` the
+        // left shift is >= `str_len`, so all the chars are removed
+        let shift_right = self.key.sub_parallelized(&str_len, &end);
+        let shift_left = self.key.add_parallelized(&shift_right, &start);
+
+        let lhs = self.right_shift_chars(str, &shift_right);
+        let mut result = self.left_shift_chars(&lhs, &shift_left);
+
+        // The result has nulls at the end unless we have kept all the chars, so we add a null
+        // because we cannot assume it isn't padded
+        result.append_null(self);
+
+        result
+    }
+
+    /// Returns the substring of an encrypted string between the `start` (inclusive) and `end`
+    /// (exclusive) byte indices, and an encrypted `BooleanBlock` that is true if the range is in
+    /// bounds, i.e. `start <= end <= len`. This is the equivalent of `str::get(start..end)`.
+    ///
+    /// If the range is out of bounds the returned string is empty. See [`ServerKey::substring`]
+    /// for the details about the indices.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use fhe_strings::*;
+    /// let (ck, sk) = gen_keys();
+    /// let s = "hello";
+    ///
+    /// let enc_s = FheString::new(&ck, &s, Some(3));
+    /// let start = UIntArg::Enc(ck.encrypt_u16(1, None));
+    ///
+    /// let (result, in_bounds) = sk.get(&enc_s, &start, &UIntArg::Clear(4));
+    /// assert_eq!(ck.decrypt_ascii(&result), "ell");
+    /// assert!(ck.key().decrypt_bool(&in_bounds));
+    ///
+    /// let (result, in_bounds) = sk.get(&enc_s, &start, &UIntArg::Clear(6));
+    /// assert_eq!(ck.decrypt_ascii(&result), "");
+    /// assert!(!ck.key().decrypt_bool(&in_bounds));
+    /// ```
+    pub fn get(
+        &self,
+        str: &FheString,
+        start: &UIntArg,
+        end: &UIntArg,
+    ) -> (FheString, BooleanBlock) {
+        if let (UIntArg::Clear(start_val), UIntArg::Clear(end_val), false) =
+            (start, end, str.is_padded())
+        {
+            let in_bounds = start_val <= end_val && *end_val as usize <= str.chars().len();
+
+            let result = if in_bounds {
+                self.substring(str, start, end)
+            } else {
+                FheString::empty()
+            };
+
+            return (result, self.key.create_trivial_boolean_block(in_bounds));
+        }
+
+        let (substring, in_bounds) = rayon::join(
+            || self.substring(str, start, end),
+            || self.range_in_bounds(str, start, end),
+        );
+
+        let result = self.conditional_string(&in_bounds, substring, &FheString::empty());
+
+        (result, in_bounds)
+    }
+
+    // Returns whether `start <= end <= len`
+    fn range_in_bounds(&self, str: &FheString, start: &UIntArg, end: &UIntArg) -> BooleanBlock {
+        let len = match self.byte_len(str) {
+            FheStringLen::Padding(enc_val) => enc_val,
+            FheStringLen::NoPadding(val) => self
+                .key
+                .create_trivial_radix(val as u32, self.index_blocks()),
+        };
+
+        let (start, end) = rayon::join(
+            || self.uint_arg_to_index(start),
+            || self.uint_arg_to_index(end),
+        );
+
+        let (start_le_end, end_le_len) = rayon::join(
+            || self.key.le_parallelized(&start, &end),
+            || self.key.le_parallelized(&end, &len),
+        );
+
+        self.key.boolean_bitand(&start_le_end, &end_le_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ciphertext::{FheString, UIntArg};
+    use crate::server_key::gen_keys;
+
+    #[test]
+    fn test_substring() {
+        let (ck, sk) = gen_keys();
+
+        let str = "hello world";
+
+        // (start, end, padding)
+        let test_cases = [
+            (0, 5, 0),
+            (6, 11, 2),
+            (3, 3, 1),
+            (4, 2, 0),
+            (8, 20, 3),
+            (20, 30, 1),
+        ];
+
+        for (start, end, pad) in test_cases {
+            let enc_str = FheString::new(&ck, str, Some(pad));
+
+            let clear_end = (end as usize).min(str.len());
+            let clear_start = (start as usize).min(clear_end);
+            let expected = &str[clear_start..clear_end];
+            let expected_get = str.get(start as usize..end as usize);
+
+            for (start, end) in [
+                (UIntArg::Clear(start), UIntArg::Clear(end)),
+                (
+                    UIntArg::Enc(ck.encrypt_u16(start, None)),
+                    UIntArg::Enc(ck.encrypt_u16(end, None)),
+                ),
+            ] {
+                let result = sk.substring(&enc_str, &start, &end);
+                assert_eq!(ck.decrypt_ascii(&result), expected);
+
+                let (result, in_bounds) = sk.get(&enc_str, &start, &end);
+                assert_eq!(ck.key().decrypt_bool(&in_bounds), expected_get.is_some());
+                assert_eq!(ck.decrypt_ascii(&result), expected_get.unwrap_or(""));
+            }
+        }
+    }
+}
@@ -1,7 +1,9 @@
 use crate::ciphertext::{FheAsciiChar, FheString};
-use crate::server_key::{FheStringIsEmpty, FheStringIterator, FheStringLen, ServerKey};
+use crate::server_key::{
+    collect_sequential, FheStringIsEmpty, FheStringIterator, FheStringLen, ServerKey,
+};
 use rayon::prelude::*;
-use tfhe::integer::BooleanBlock;
+use tfhe::integer::{BooleanBlock, RadixCiphertext};
 
 pub struct SplitAsciiWhitespace {
     state: FheString,
@@ -40,10 +42,73 @@ impl FheStringIterator for SplitAsciiWhitespace {
             },
         )
     }
+
+    fn collect_bounded(
+        self,
+        sk: &ServerKey,
+        max_items: usize,
+    ) -> (Vec<(FheString, BooleanBlock)>, RadixCiphertext) {
+        // Once `next` is called the state is only the remaining string
+        if self.current_mask.is_some() {
+            return collect_sequential(self, sk, max_items);
+        }
+
+        let str = &self.state;
+        let is_ws: Vec<_> = str
+            .chars()
+            .par_iter()
+            .map(|char| sk.is_whitespace(char, true))
+            .collect();
+        let len = is_ws.len();
+
+        // Each item starts after a whitespace (or at the start) and ends before the next one (or
+        // at the end), so we find the nth start and end of all the items in parallel
+        let (starts, ends): (Vec<_>, Vec<_>) = (0..=len)
+            .into_par_iter()
+            .map(|i| {
+                let starts = (i < len).then(|| {
+                    let mut is_start = sk.key.boolean_bitnot(&is_ws[i]);
+                    if i > 0 {
+                        sk.key.boolean_bitand_assign(&mut is_start, &is_ws[i - 1]);
+                    }
+
+                    (i, is_start)
+                });
+                let ends = (i > 0).then(|| {
+                    let mut is_end = sk.key.boolean_bitnot(&is_ws[i - 1]);
+                    if i < len {
+                        sk.key.boolean_bitand_assign(&mut is_end, &is_ws[i]);
+                    }
+
+                    (i, is_end)
+                });
+
+                (starts, ends)
+            })
+            .unzip();
+
+        let starts: Vec<_> = starts.into_iter().flatten().collect();
+        let ends: Vec<_> = ends.into_iter().flatten().collect();
+
+        let (starts, ends) = rayon::join(
+            || sk.first_set_positions(&starts, max_items),
+            || sk.first_set_positions(&ends, max_items),
+        );
+
+        let items: Vec<_> = starts
+            .into_par_iter()
+            .zip(ends)
+            .map(|((start, is_some), (end, _))| (sk.substring_between(str, &start, &end), is_some))
+            .collect();
+
+        let count = sk.count_some(&items);
+
+        (items, count)
+    }
 }
 
 impl SplitAsciiWhitespace {
-    // The mask contains 255u8 until we find some whitespace, then will be 0u8
+    // The mask chars have all bits set until we find some whitespace, then will be 0
     fn create_and_apply_mask(&mut self, sk: &ServerKey) -> FheString {
         let mut mask = self.state.clone();
         let mut result = self.state.clone();
@@ -53,9 +118,9 @@ impl SplitAsciiWhitespace {
             let mut is_not_ws = sk.is_not_whitespace(char);
             sk.key.boolean_bitand_assign(&mut is_not_ws, &prev_was_not);
 
-            let mut mask_u8 = is_not_ws.clone().into_radix(4, &sk.key);
+            let mut mask_u8 = is_not_ws.clone().into_radix(sk.char_blocks(), &sk.key);
 
-            // 0u8 is kept the same, but 1u8 is transformed into 255u8
+            // 0 is kept the same, but 1 is transformed into a char with all bits set
             sk.key.scalar_sub_assign_parallelized(&mut mask_u8, 1);
             sk.key.bitnot_assign_parallelized(&mut mask_u8);
 
@@ -84,9 +149,9 @@ impl SplitAsciiWhitespace {
     fn remaining_string(&mut self, sk: &ServerKey) {
         let mask = self.current_mask.as_ref().unwrap();
 
-        let mut number_of_trues = sk.key.create_trivial_zero_radix(16);
+        let mut number_of_trues = sk.key.create_trivial_zero_radix(sk.index_blocks());
         for mask_u8 in mask.chars() {
-            let is_true = sk.key.scalar_eq_parallelized(mask_u8.ciphertext(), 255u8);
+            let is_true = sk.key.scalar_ne_parallelized(mask_u8.ciphertext(), 0u8);
             sk.key
                 .add_assign_parallelized(&mut number_of_trues, &is_true.into_radix(1, &sk.key));
         }
@@ -170,7 +235,7 @@ impl ServerKey {
 
             *char.ciphertext_mut() = self.key.if_then_else_parallelized(
                 &is_whitespace,
-                &self.key.create_trivial_zero_radix(4),
+                &self.key.create_trivial_zero_radix(self.char_blocks()),
                 char.ciphertext(),
             );
 
@@ -183,7 +248,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let s = "  hello world";
     ///
@@ -207,10 +273,12 @@ impl ServerKey {
         // before and after the trimming, and use that amount to shift the result left. This
         // makes the result nulls be at the end
         result.set_is_padded(true);
-        if let FheStringLen::Padding(len_after_trim) = self.len(&result) {
-            let original_str_len = match self.len(str) {
+        if let FheStringLen::Padding(len_after_trim) = self.byte_len(&result) {
+            let original_str_len = match self.byte_len(str) {
                 FheStringLen::Padding(enc_val) => enc_val,
-                FheStringLen::NoPadding(val) => self.key.create_trivial_radix(val as u32, 16),
+                FheStringLen::NoPadding(val) => self
+                    .key
+                    .create_trivial_radix(val as u32, self.index_blocks()),
             };
 
             let shift_left = self
@@ -236,7 +304,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let s = "hello world  ";
     ///
@@ -273,7 +342,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let s = "  hello world  ";
     ///
@@ -304,7 +374,8 @@ impl ServerKey {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
+    /// # use fhe_strings::*;
     /// let (ck, sk) = gen_keys();
     /// let s = "hello \t\nworld ";
     ///
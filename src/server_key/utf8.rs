@@ -0,0 +1,275 @@
+use crate::ciphertext::FheString;
+use crate::server_key::ServerKey;
+use rayon::prelude::*;
+use tfhe::integer::{BooleanBlock, RadixCiphertext};
+
+impl ServerKey {
+    // Returns the number of UTF-8 chars, counting every byte that isn't a null or a continuation
+    // byte (see `ServerKey::len`)
+    pub(crate) fn chars_count(&self, str: &FheString) -> RadixCiphertext {
+        let (leading_bytes, _) = self.leading_and_continuation_bytes(str);
+
+        let leading_bytes: Vec<_> = leading_bytes
+            .into_par_iter()
+            .map(|is_leading| is_leading.into_radix(self.index_blocks(), &self.key))
+            .collect();
+
+        self.key
+            .sum_ciphertexts_parallelized(leading_bytes.iter())
+            .unwrap_or_else(|| self.key.create_trivial_zero_radix(self.index_blocks()))
+    }
+
+    // Returns, for each byte, whether it starts a char (i.e. it's not null nor a continuation byte)
+    // and whether it's a continuation byte (of the form 0b10xxxxxx)
+    fn leading_and_continuation_bytes(
+        &self,
+        str: &FheString,
+    ) -> (Vec<BooleanBlock>, Vec<BooleanBlock>) {
+        str.chars()
+            .par_iter()
+            .map(|char| {
+                let (is_continuation, is_null) = rayon::join(
+                    || {
+                        let masked = self
+                            .key
+                            .scalar_bitand_parallelized(char.ciphertext(), 0xC0u8);
+
+                        self.key.scalar_eq_parallelized(&masked, 0x80u8)
+                    },
+                    || self.key.scalar_eq_parallelized(char.ciphertext(), 0u8),
+                );
+
+                let not_leading = self.key.boolean_bitor(&is_continuation, &is_null);
+
+                (self.key.boolean_bitnot(&not_leading), is_continuation)
+            })
+            .unzip()
+    }
+
+    // Returns, for each index from 0 to the number of chars (included), whether it's a char
+    // boundary of the string, i.e. it doesn't come after a padding null nor before a continuation
+    // byte
+    pub(crate) fn char_boundaries(&self, str: &FheString) -> Vec<BooleanBlock> {
+        let (leading_bytes, continuation_bytes) = self.leading_and_continuation_bytes(str);
+        let len = leading_bytes.len();
+
+        (0..len + 1)
+            .into_par_iter()
+            .map(|i| {
+                // The previous byte isn't a null if it's a leading or a continuation byte
+                let after_char = (i > 0 && str.is_padded()).then(|| {
+                    self.key
+                        .boolean_bitor(&leading_bytes[i - 1], &continuation_bytes[i - 1])
+                });
+                let before_char =
+                    (i < len).then(|| self.key.boolean_bitnot(&continuation_bytes[i]));
+
+                match (after_char, before_char) {
+                    (Some(after_char), Some(before_char)) => {
+                        self.key.boolean_bitand(&after_char, &before_char)
+                    }
+                    (Some(is_boundary), None) | (None, Some(is_boundary)) => is_boundary,
+                    (None, None) => self.key.create_trivial_boolean_block(true),
+                }
+            })
+            .collect()
+    }
+
+    // Returns the number of bytes of the first char, which is given by its leading byte. An empty
+    // string (or one that starts with a null) is treated as having a 1 byte char
+    pub(crate) fn first_char_width(&self, str: &FheString) -> RadixCiphertext {
+        let mut width = self.key.create_trivial_radix(1u32, self.index_blocks());
+
+        let Some(first) = str.chars().first() else {
+            return width;
+        };
+
+        // Leading bytes of 2, 3 and 4 bytes chars are >= 0xC0, 0xE0 and 0xF0 respectively
+        let extra_bytes: Vec<_> = [0xC0u8, 0xE0, 0xF0]
+            .into_par_iter()
+            .map(|leading| {
+                self.key
+                    .scalar_ge_parallelized(first.ciphertext(), leading)
+                    .into_radix(self.index_blocks(), &self.key)
+            })
+            .collect();
+
+        for extra in extra_bytes {
+            self.key.add_assign_parallelized(&mut width, &extra);
+        }
+
+        width
+    }
+
+    // Returns the number of bytes of the last char (ignoring the padding), computed as 1 plus the
+    // number of continuation bytes that come after the last leading byte. An empty string is
+    // treated as having a 1 byte char
+    pub(crate) fn last_char_width(&self, str: &FheString) -> RadixCiphertext {
+        let (leading_bytes, continuation_bytes) = self.leading_and_continuation_bytes(str);
+
+        let mut leading_after = self.key.create_trivial_boolean_block(false);
+        let mut last_char_bytes = vec![];
+
+        for (is_leading, is_continuation) in leading_bytes.iter().zip(continuation_bytes).rev() {
+            let not_leading_after = self.key.boolean_bitnot(&leading_after);
+            let is_last_char_byte = self
+                .key
+                .boolean_bitand(&is_continuation, &not_leading_after);
+
+            last_char_bytes.push(is_last_char_byte.into_radix(self.index_blocks(), &self.key));
+
+            self.key
+                .boolean_bitor_assign(&mut leading_after, is_leading);
+        }
+
+        last_char_bytes.push(self.key.create_trivial_radix(1u32, self.index_blocks()));
+
+        self.key
+            .sum_ciphertexts_parallelized(last_char_bytes.iter())
+            .expect("There's at least the leading byte")
+    }
+
+    // Returns, for each char, whether it's an ASCII letter in the `ascii` range or a Latin-1
+    // Supplement letter, i.e. a byte in the `latin1` range (other than `excluded`, which is the
+    // multiplication or division sign) preceded by the 0xC3 leading byte. In both cases changing
+    // the letter case just requires adding or subtracting 32.
+    pub(crate) fn case_change_mask(
+        &self,
+        str: &FheString,
+        ascii: (u8, u8),
+        latin1: (u8, u8),
+        excluded: u8,
+    ) -> Vec<BooleanBlock> {
+        let chars = str.chars();
+
+        chars
+            .par_iter()
+            .enumerate()
+            .map(|(i, char)| {
+                let in_range = |(low, high): (u8, u8)| {
+                    let (ge_low, le_high) = rayon::join(
+                        || self.key.scalar_ge_parallelized(char.ciphertext(), low),
+                        || self.key.scalar_le_parallelized(char.ciphertext(), high),
+                    );
+
+                    self.key.boolean_bitand(&ge_low, &le_high)
+                };
+
+                // The first byte cannot be a continuation byte
+                if i == 0 {
+                    return in_range(ascii);
+                }
+
+                let ((is_ascii_letter, in_latin1_range), (prev_is_c3, not_excluded)) = rayon::join(
+                    || rayon::join(|| in_range(ascii), || in_range(latin1)),
+                    || {
+                        rayon::join(
+                            || {
+                                self.key
+                                    .scalar_eq_parallelized(chars[i - 1].ciphertext(), 0xC3u8)
+                            },
+                            || self.key.scalar_ne_parallelized(char.ciphertext(), excluded),
+                        )
+                    },
+                );
+
+                let mut is_latin1_letter = self.key.boolean_bitand(&in_latin1_range, &prev_is_c3);
+                self.key
+                    .boolean_bitand_assign(&mut is_latin1_letter, &not_excluded);
+
+                self.key.boolean_bitor(&is_ascii_letter, &is_latin1_letter)
+            })
+            .collect()
+    }
+}
+
+// Clear equivalent of `ServerKey::to_lowercase`, which only changes the case of ASCII and Latin-1
+// Supplement letters (unlike `str::to_lowercase`)
+pub(crate) fn latin1_to_lowercase(str: &str) -> String {
+    str.chars()
+        .map(|char| {
+            if char.is_ascii_uppercase() || (('À'..='Þ').contains(&char) && char != '×') {
+                char::from_u32(char as u32 + 32).unwrap()
+            } else {
+                char
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ciphertext::{ClearString, FheString, GenericPattern};
+    use crate::server_key::{gen_keys, FheStringCharLen, FheStringIterator};
+
+    #[test]
+    fn test_len_and_case() {
+        let (ck, sk) = gen_keys();
+
+        let test_cases = [
+            ("", 1, "", ""),
+            ("Ñandú", 0, "ÑANDÚ", "ñandú"),
+            ("Ñandú", 2, "ÑANDÚ", "ñandú"),
+            ("Ä ß ÿ × ö ÷ 東", 1, "Ä ß ÿ × Ö ÷ 東", "ä ß ÿ × ö ÷ 東"),
+        ];
+
+        for (str, pad, upper, lower) in test_cases {
+            let enc_str = FheString::new_utf8(&ck, str, Some(pad));
+
+            let count = match sk.len(&enc_str) {
+                FheStringCharLen::Clear(len) => len,
+                FheStringCharLen::Enc(enc_len) => ck.key().decrypt_radix::<u32>(&enc_len) as usize,
+            };
+            assert_eq!(count, str.chars().count());
+
+            assert_eq!(ck.decrypt_utf8(&sk.to_uppercase(&enc_str)), upper);
+            assert_eq!(ck.decrypt_utf8(&sk.to_lowercase(&enc_str)), lower);
+        }
+
+        let enc_str = FheString::new_utf8(&ck, "Ñandú", None);
+        let pat = GenericPattern::Clear(ClearString::new("ñANDÚ".to_string()));
+
+        assert!(ck.key().decrypt_bool(&sk.eq_ignore_case(&enc_str, &pat)));
+    }
+
+    #[test]
+    fn test_utf8_patterns() {
+        let (ck, sk) = gen_keys();
+
+        let str = "añ東b";
+        let enc_str = FheString::new_utf8(&ck, str, Some(1));
+
+        let pat = GenericPattern::Clear(ClearString::new("東".to_string()));
+        let (index, is_some) = sk.find(&enc_str, &pat);
+
+        assert!(ck.key().decrypt_bool(&is_some));
+        assert_eq!(ck.key().decrypt_radix::<u32>(&index), 3);
+
+        // An empty pattern matches at every char boundary
+        let empty = GenericPattern::Clear(ClearString::new(String::new()));
+
+        let mut split_iter = sk.split(&enc_str, &empty);
+        let mut rsplit_iter = sk.rsplit(&enc_str, &empty);
+
+        for (expected, expected_rev) in str.split("").zip(str.rsplit("")) {
+            let (item, is_some) = split_iter.next(&sk);
+            let (item_rev, is_some_rev) = rsplit_iter.next(&sk);
+
+            assert!(ck.key().decrypt_bool(&is_some));
+            assert!(ck.key().decrypt_bool(&is_some_rev));
+            assert_eq!(ck.decrypt_utf8(&item), expected);
+            assert_eq!(ck.decrypt_utf8(&item_rev), expected_rev);
+        }
+
+        let (_, no_more_items) = split_iter.next(&sk);
+        let (_, no_more_items_rev) = rsplit_iter.next(&sk);
+
+        assert!(!ck.key().decrypt_bool(&no_more_items));
+        assert!(!ck.key().decrypt_bool(&no_more_items_rev));
+
+        let to = FheString::new_utf8(&ck, "é", None);
+        let replaced = sk.replace(&enc_str, &empty, &to);
+
+        assert_eq!(ck.decrypt_utf8(&replaced), "éaéñé東ébé");
+    }
+}